@@ -2,13 +2,16 @@
 
 use anchor_lang::{prelude::*, solana_program::keccak::hashv};
 use mpl_bubblegum::{
-    hash::hash_creators,
+    hash::{hash_creators, hash_metadata},
     instructions::TransferCpiBuilder,
-    types::{Creator, MetadataArgs},
+    types::{Creator, LeafSchema, MetadataArgs},
     utils::get_asset_id,
 };
 
-use crate::TCreator;
+use crate::{cpi_invoke, TCreator, TensorError};
+
+/// Anchor discriminator of the SPL Account Compression `verify_leaf` instruction.
+const VERIFY_LEAF_DISCRIMINATOR: [u8; 8] = [124, 220, 22, 223, 104, 10, 250, 224];
 
 //into bubblgum
 impl From<TCreator> for Creator {
@@ -74,6 +77,15 @@ pub fn transfer_cnft(args: TransferArgs) -> Result<()> {
         signer_seeds,
     } = args;
 
+    assert_cnft_programs(log_wrapper, compression_program, bubblegum_program)?;
+
+    // Transferring to the default pubkey would effectively burn the cNFT. The new owner is not
+    // required to be on curve, since PDAs (e.g. escrows) can own cNFTs.
+    require!(
+        *new_leaf_owner.key != Pubkey::default(),
+        TensorError::InvalidDestination
+    );
+
     let (owner_signer, delegate_signer) = if let Some(signer) = signer {
         (
             leaf_owner.is_signer || signer.key() == leaf_owner.key(),
@@ -112,6 +124,157 @@ pub fn transfer_cnft(args: TransferArgs) -> Result<()> {
     Ok(())
 }
 
+pub struct VerifyLeafArgs<'a, 'info> {
+    pub root: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub leaf_owner: &'a Pubkey,
+    pub leaf_delegate: &'a Pubkey,
+    pub merkle_tree: &'a AccountInfo<'info>,
+    pub compression_program: &'a AccountInfo<'info>,
+    pub proof_accounts: &'a [AccountInfo<'info>],
+}
+
+/// Verifies that the leaf owner owns the cNFT.
+///
+/// Rebuilds the leaf from the asset id, owner, delegate, nonce, data hash and creator hash,
+/// and verifies it against the root and proof with the SPL Account Compression program, which
+/// completes the proof from the tree canopy.
+pub fn verify_leaf_ownership(args: VerifyLeafArgs) -> Result<()> {
+    let VerifyLeafArgs {
+        root,
+        nonce,
+        index,
+        data_hash,
+        creator_hash,
+        leaf_owner,
+        leaf_delegate,
+        merkle_tree,
+        compression_program,
+        proof_accounts,
+    } = args;
+
+    let leaf = compute_cnft_leaf(
+        &get_asset_id(merkle_tree.key, nonce),
+        leaf_owner,
+        leaf_delegate,
+        nonce,
+        &data_hash,
+        &creator_hash,
+    );
+
+    let mut data = VERIFY_LEAF_DISCRIMINATOR.to_vec();
+    data.extend(root);
+    data.extend(leaf);
+    data.extend(index.to_le_bytes());
+
+    let mut accounts = vec![AccountMeta::new_readonly(*merkle_tree.key, false)];
+    accounts.extend(
+        proof_accounts
+            .iter()
+            .map(|proof| AccountMeta::new_readonly(*proof.key, false)),
+    );
+
+    let mut account_infos = vec![merkle_tree.clone()];
+    account_infos.extend_from_slice(proof_accounts);
+
+    cpi_invoke(
+        compression_program,
+        &spl_account_compression::ID,
+        accounts,
+        data,
+        &account_infos,
+        None,
+    )
+}
+
+/// Computes the leaf node of a cNFT, as stored by Bubblegum in the merkle tree.
+///
+/// The node is the keccak hash of the leaf schema version, asset id, owner, delegate, nonce,
+/// data hash and creator hash; it can be compared against the leaf reported by indexers.
+pub fn compute_cnft_leaf(
+    asset_id: &Pubkey,
+    owner: &Pubkey,
+    delegate: &Pubkey,
+    nonce: u64,
+    data_hash: &[u8; 32],
+    creator_hash: &[u8; 32],
+) -> [u8; 32] {
+    LeafSchema::V1 {
+        id: *asset_id,
+        owner: *owner,
+        delegate: *delegate,
+        nonce,
+        data_hash: *data_hash,
+        creator_hash: *creator_hash,
+    }
+    .hash()
+}
+
+/// Derives the Bubblegum tree authority (tree config) PDA of a merkle tree.
+pub fn derive_tree_authority(merkle_tree: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[merkle_tree.as_ref()], &mpl_bubblegum::ID)
+}
+
+/// Asserts that the programs used by the Bubblegum CPI are the canonical SPL Noop,
+/// SPL Account Compression and Bubblegum programs.
+///
+/// Prevents a caller from substituting impostor programs in the cNFT instructions.
+pub fn assert_cnft_programs(
+    log_wrapper: &AccountInfo,
+    compression_program: &AccountInfo,
+    bubblegum_program: &AccountInfo,
+) -> Result<()> {
+    require_keys_eq!(*log_wrapper.key, spl_noop::ID, ErrorCode::InvalidProgramId);
+    require_keys_eq!(
+        *compression_program.key,
+        spl_account_compression::ID,
+        ErrorCode::InvalidProgramId
+    );
+    require_keys_eq!(
+        *bubblegum_program.key,
+        mpl_bubblegum::ID,
+        ErrorCode::InvalidProgramId
+    );
+
+    Ok(())
+}
+
+/// Verifies that the provided creators hash to the expected creator hash committed in the leaf.
+///
+/// Prevents a seller from lying about the creators of a cNFT.
+pub fn verify_creator_hash(creators: &[Creator], expected: &[u8; 32]) -> Result<()> {
+    let creator_hash = hash_creators(creators);
+
+    require!(creator_hash == *expected, TensorError::CreatorMismatch);
+
+    Ok(())
+}
+
+/// Verifies that the provided metadata hashes to the expected data hash committed in the leaf.
+///
+/// Prevents a seller from listing a cNFT with misleading metadata (e.g. a different URI).
+pub fn verify_metadata_matches_data_hash(
+    metadata: &MetadataArgs,
+    expected_data_hash: &[u8; 32],
+) -> Result<()> {
+    let data_hash = hash_metadata(metadata)?;
+
+    require!(data_hash == *expected_data_hash, TensorError::BadMetadata);
+
+    Ok(())
+}
+
+/// Compares two metadata by their data hash, which is how a leaf commits to its metadata.
+///
+/// The data hash covers all metadata fields, so this is equivalent to a field-by-field
+/// comparison from the point of view of the leaf.
+pub fn metadata_args_eq_by_hash(a: &MetadataArgs, b: &MetadataArgs) -> Result<bool> {
+    Ok(hash_metadata(a)? == hash_metadata(b)?)
+}
+
 pub enum MetadataSrc {
     Metadata(MetadataArgs),
     DataHash(DataHashArgs),
@@ -138,6 +301,29 @@ pub struct CnftArgs {
     pub creators: Vec<Creator>,
 }
 
+/// Standardized cNFT listing data, so programs can emit the same event when listing a cNFT.
+///
+/// Creators are stored as `TCreator` so the event layout does not depend on the bubblegum types.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CnftListingData {
+    pub asset_id: Pubkey,
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub creators: Vec<TCreator>,
+}
+
+impl From<CnftArgs> for CnftListingData {
+    fn from(args: CnftArgs) -> Self {
+        CnftListingData {
+            asset_id: args.asset_id,
+            data_hash: args.data_hash,
+            creator_hash: args.creator_hash,
+            creators: args.creators.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 pub fn make_cnft_args(args: MakeCnftArgs) -> Result<CnftArgs> {
     let MakeCnftArgs {
         metadata_src,
@@ -151,12 +337,7 @@ pub fn make_cnft_args(args: MakeCnftArgs) -> Result<CnftArgs> {
     let (data_hash, creator_hash, creators) = match metadata_src {
         MetadataSrc::Metadata(mplex_metadata) => {
             let creator_hash = hash_creators(&mplex_metadata.creators);
-            let metadata_args_hash = hashv(&[mplex_metadata.try_to_vec()?.as_slice()]);
-            let data_hash = hashv(&[
-                &metadata_args_hash.to_bytes(),
-                &mplex_metadata.seller_fee_basis_points.to_le_bytes(),
-            ])
-            .to_bytes();
+            let data_hash = hash_metadata(&mplex_metadata)?;
 
             (data_hash, creator_hash, mplex_metadata.creators)
         }
@@ -169,6 +350,29 @@ pub fn make_cnft_args(args: MakeCnftArgs) -> Result<CnftArgs> {
             // Verify seller fee basis points
             let data_hash = hashv(&[&meta_hash, &seller_fee_basis_points.to_le_bytes()]).to_bytes();
             // Verify creators
+            if creator_accounts.len() != creator_shares.len()
+                || creator_accounts.len() != creator_verified.len()
+            {
+                msg!(
+                    "Creator length mismatch: {} accounts, {} shares, {} verified flags",
+                    creator_accounts.len(),
+                    creator_shares.len(),
+                    creator_verified.len()
+                );
+                return Err(TensorError::CreatorMismatch.into());
+            }
+            // Bubblegum rejects duplicate creators on mint, so a leaf with duplicates cannot
+            // exist and the creator hash would never match
+            for (index, creator) in creator_accounts.iter().enumerate() {
+                if creator_accounts[..index]
+                    .iter()
+                    .any(|other| other.key == creator.key)
+                {
+                    msg!("Duplicate creator {} at index {}", creator.key, index);
+                    return Err(TensorError::DuplicateCreator.into());
+                }
+            }
+
             let creators = creator_accounts
                 .iter()
                 .zip(creator_shares.iter())
@@ -192,3 +396,512 @@ pub fn make_cnft_args(args: MakeCnftArgs) -> Result<CnftArgs> {
         creators,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{setup_syscall_stubs, take_invoked_instructions};
+
+    fn test_creators() -> Vec<Creator> {
+        vec![
+            Creator {
+                address: Pubkey::new_from_array([1u8; 32]),
+                verified: true,
+                share: 60,
+            },
+            Creator {
+                address: Pubkey::new_from_array([2u8; 32]),
+                verified: false,
+                share: 40,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_verify_creator_hash() {
+        let creators = test_creators();
+        let expected = hash_creators(&creators);
+
+        assert!(verify_creator_hash(&creators, &expected).is_ok());
+    }
+
+    #[test]
+    fn test_verify_creator_hash_mismatch() {
+        let expected = hash_creators(&test_creators());
+
+        // different share
+        let mut creators = test_creators();
+        creators[0].share = 50;
+        creators[1].share = 50;
+        assert!(verify_creator_hash(&creators, &expected).is_err());
+
+        // different verified flag
+        let mut creators = test_creators();
+        creators[1].verified = true;
+        assert!(verify_creator_hash(&creators, &expected).is_err());
+
+        // missing creator
+        let creators = &test_creators()[..1];
+        assert!(verify_creator_hash(creators, &expected).is_err());
+    }
+
+    fn test_metadata_args() -> MetadataArgs {
+        MetadataArgs {
+            name: "Test".to_string(),
+            symbol: "TEST".to_string(),
+            uri: "https://test.com/1.json".to_string(),
+            seller_fee_basis_points: 500,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: None,
+            uses: None,
+            token_program_version: mpl_bubblegum::types::TokenProgramVersion::Original,
+            creators: test_creators(),
+        }
+    }
+
+    #[test]
+    fn test_metadata_args_eq_by_hash() {
+        let metadata = test_metadata_args();
+        assert!(metadata_args_eq_by_hash(&metadata, &test_metadata_args()).unwrap());
+
+        // differing uri
+        let other = MetadataArgs {
+            uri: "https://test.com/2.json".to_string(),
+            ..test_metadata_args()
+        };
+        assert!(!metadata_args_eq_by_hash(&metadata, &other).unwrap());
+
+        // differing royalties, which are also committed in the data hash
+        let other = MetadataArgs {
+            seller_fee_basis_points: 1_000,
+            ..test_metadata_args()
+        };
+        assert!(!metadata_args_eq_by_hash(&metadata, &other).unwrap());
+    }
+
+    #[test]
+    fn test_verify_metadata_matches_data_hash() {
+        let metadata = test_metadata_args();
+
+        // Data hash of the test metadata, computed independently of bubblegum with a standalone
+        // keccak-256: keccak(keccak(borsh(metadata)) || seller_fee_basis_points (LE)).
+        let expected = [
+            69, 13, 208, 149, 179, 111, 186, 148, 75, 94, 56, 85, 57, 237, 234, 134, 46, 241, 146,
+            197, 24, 217, 178, 192, 89, 128, 106, 28, 188, 248, 36, 226,
+        ];
+
+        assert!(verify_metadata_matches_data_hash(&metadata, &expected).is_ok());
+
+        // tampered uri
+        let tampered = MetadataArgs {
+            uri: "https://test.com/2.json".to_string(),
+            ..test_metadata_args()
+        };
+        assert_eq!(
+            verify_metadata_matches_data_hash(&tampered, &expected),
+            Err(TensorError::BadMetadata.into())
+        );
+
+        // tampered royalties
+        let tampered = MetadataArgs {
+            seller_fee_basis_points: 0,
+            ..test_metadata_args()
+        };
+        assert!(verify_metadata_matches_data_hash(&tampered, &expected).is_err());
+    }
+
+    #[test]
+    fn test_compute_cnft_leaf() {
+        // Test vector computed independently of bubblegum, with a standalone keccak-256 over the
+        // V1 leaf layout: version (1), asset id, owner, delegate, nonce (LE), data hash and
+        // creator hash.
+        let asset_id = Pubkey::new_from_array([3u8; 32]);
+        let (owner, delegate) = (
+            Pubkey::new_from_array([1u8; 32]),
+            Pubkey::new_from_array([2u8; 32]),
+        );
+        let nonce = 42;
+        let data_hash = [
+            69, 13, 208, 149, 179, 111, 186, 148, 75, 94, 56, 85, 57, 237, 234, 134, 46, 241, 146,
+            197, 24, 217, 178, 192, 89, 128, 106, 28, 188, 248, 36, 226,
+        ];
+        let creator_hash = [
+            3, 178, 187, 65, 184, 60, 131, 244, 254, 195, 204, 1, 103, 25, 222, 155, 104, 206, 133,
+            145, 211, 184, 24, 129, 104, 221, 232, 60, 181, 244, 105, 194,
+        ];
+        let expected = [
+            83, 241, 188, 110, 168, 84, 20, 8, 126, 75, 1, 159, 122, 127, 67, 61, 179, 165, 17, 22,
+            201, 230, 89, 18, 60, 147, 222, 86, 93, 116, 41, 1,
+        ];
+
+        let leaf = compute_cnft_leaf(
+            &asset_id,
+            &owner,
+            &delegate,
+            nonce,
+            &data_hash,
+            &creator_hash,
+        );
+        assert_eq!(leaf, expected);
+
+        // any component changes the leaf
+        assert_ne!(
+            compute_cnft_leaf(
+                &asset_id,
+                &delegate,
+                &owner,
+                nonce,
+                &data_hash,
+                &creator_hash
+            ),
+            leaf
+        );
+        assert_ne!(
+            compute_cnft_leaf(
+                &asset_id,
+                &owner,
+                &delegate,
+                nonce + 1,
+                &data_hash,
+                &creator_hash
+            ),
+            leaf
+        );
+    }
+
+    #[test]
+    fn test_verify_leaf_ownership() {
+        setup_syscall_stubs();
+
+        let merkle_tree = account_info(Pubkey::new_unique(), false);
+        let compression_program = account_info(spl_account_compression::ID, true);
+        let (owner, delegate) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (nonce, index) = (5, 5);
+        let (data_hash, creator_hash) = ([1u8; 32], hash_creators(&test_creators()));
+
+        let leaf = LeafSchema::V1 {
+            id: get_asset_id(merkle_tree.key, nonce),
+            owner,
+            delegate,
+            nonce,
+            data_hash,
+            creator_hash,
+        }
+        .hash();
+
+        // depth 3 tree with the leaf at index 5
+        let mut nodes: Vec<[u8; 32]> = (0..8u8).map(|i| [i + 100; 32]).collect();
+        nodes[index as usize] = leaf;
+        let mut proof = vec![];
+        let mut node_index = index as usize;
+        while nodes.len() > 1 {
+            proof.push(nodes[node_index ^ 1]);
+            nodes = nodes
+                .chunks(2)
+                .map(|pair| hashv(&[&pair[0], &pair[1]]).to_bytes())
+                .collect();
+            node_index /= 2;
+        }
+        let root = nodes[0];
+
+        let proof_accounts = proof
+            .iter()
+            .map(|node| account_info(Pubkey::new_from_array(*node), false))
+            .collect::<Vec<_>>();
+
+        let args = |leaf_owner| VerifyLeafArgs {
+            root,
+            nonce,
+            index,
+            data_hash,
+            creator_hash,
+            leaf_owner,
+            leaf_delegate: &delegate,
+            merkle_tree: &merkle_tree,
+            compression_program: &compression_program,
+            proof_accounts: &proof_accounts,
+        };
+
+        verify_leaf_ownership(args(&owner)).unwrap();
+
+        let instructions = take_invoked_instructions();
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].program_id, spl_account_compression::ID);
+
+        let mut data = VERIFY_LEAF_DISCRIMINATOR.to_vec();
+        data.extend(root);
+        data.extend(leaf);
+        data.extend(index.to_le_bytes());
+        assert_eq!(instructions[0].data, data);
+
+        let keys = instructions[0]
+            .accounts
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect::<Vec<_>>();
+        assert_eq!(keys[0], *merkle_tree.key);
+        assert_eq!(
+            keys[1..],
+            proof
+                .iter()
+                .map(|node| Pubkey::new_from_array(*node))
+                .collect::<Vec<_>>()
+        );
+
+        // a different owner produces a different leaf
+        let other_owner = Pubkey::new_unique();
+        verify_leaf_ownership(args(&other_owner)).unwrap();
+        assert_ne!(take_invoked_instructions()[0].data, data);
+
+        // impostor compression program
+        let impostor = account_info(Pubkey::new_unique(), true);
+        assert!(verify_leaf_ownership(VerifyLeafArgs {
+            compression_program: &impostor,
+            ..args(&owner)
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_derive_tree_authority() {
+        let merkle_tree = Pubkey::new_from_array([7u8; 32]);
+        let (tree_authority, bump) = derive_tree_authority(&merkle_tree);
+
+        assert_eq!(
+            tree_authority,
+            crate::pubkey("916P5Yyt21jJ9AbmBJKiHbUWbiqDzhcb12qkrjU2xvVt")
+        );
+        assert_eq!(bump, 238);
+        assert_eq!(
+            (tree_authority, bump),
+            mpl_bubblegum::accounts::TreeConfig::find_pda(&merkle_tree)
+        );
+    }
+
+    #[test]
+    fn test_make_cnft_args_creator_length_mismatch() {
+        let merkle_tree = account_info(Pubkey::new_unique(), false);
+        let creator_accounts = [
+            account_info(Pubkey::new_unique(), false),
+            account_info(Pubkey::new_unique(), false),
+        ];
+
+        let args = |creator_shares: Vec<u8>, creator_verified: Vec<bool>| MakeCnftArgs {
+            nonce: 0,
+            metadata_src: MetadataSrc::DataHash(DataHashArgs {
+                meta_hash: [0; 32],
+                creator_shares,
+                creator_verified,
+                seller_fee_basis_points: 500,
+            }),
+            merkle_tree: &merkle_tree,
+            creator_accounts: &creator_accounts,
+        };
+
+        let cnft_args = make_cnft_args(args(vec![60, 40], vec![true, false])).unwrap();
+        assert_eq!(cnft_args.creators.len(), 2);
+
+        for (creator_shares, creator_verified) in [
+            (vec![100], vec![true, false]),
+            (vec![60, 40], vec![true]),
+            (vec![50, 25, 25], vec![true, false, false]),
+        ] {
+            assert!(matches!(
+                make_cnft_args(args(creator_shares, creator_verified)),
+                Err(error) if error == TensorError::CreatorMismatch.into()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_cnft_listing_data() {
+        let merkle_tree = account_info(Pubkey::new_unique(), false);
+        let creators = test_creators();
+        let creator_accounts = [
+            account_info(creators[0].address, false),
+            account_info(creators[1].address, false),
+        ];
+
+        let cnft_args = make_cnft_args(MakeCnftArgs {
+            nonce: 7,
+            metadata_src: MetadataSrc::DataHash(DataHashArgs {
+                meta_hash: [9; 32],
+                creator_shares: vec![60, 40],
+                creator_verified: vec![true, false],
+                seller_fee_basis_points: 500,
+            }),
+            merkle_tree: &merkle_tree,
+            creator_accounts: &creator_accounts,
+        })
+        .unwrap();
+        let (asset_id, data_hash, creator_hash) = (
+            cnft_args.asset_id,
+            cnft_args.data_hash,
+            cnft_args.creator_hash,
+        );
+
+        let listing = CnftListingData::from(cnft_args);
+        assert_eq!(listing.asset_id, asset_id);
+        assert_eq!(listing.asset_id, get_asset_id(merkle_tree.key, 7));
+        assert_eq!(listing.data_hash, data_hash);
+        assert_eq!(listing.creator_hash, creator_hash);
+        assert_eq!(
+            listing.creators,
+            creators.into_iter().map(TCreator::from).collect::<Vec<_>>()
+        );
+
+        // event data is the discriminator followed by the serialized listing
+        let data = anchor_lang::Event::data(&listing);
+        assert_eq!(
+            &data[..8],
+            &<CnftListingData as anchor_lang::Discriminator>::DISCRIMINATOR
+        );
+        assert_eq!(
+            CnftListingData::try_from_slice(&data[8..]).unwrap(),
+            listing
+        );
+    }
+
+    #[test]
+    fn test_make_cnft_args_duplicate_creators() {
+        let merkle_tree = account_info(Pubkey::new_unique(), false);
+        let creator = Pubkey::new_unique();
+        let creator_accounts = [
+            account_info(creator, false),
+            account_info(Pubkey::new_unique(), false),
+            account_info(creator, false),
+        ];
+
+        let result = make_cnft_args(MakeCnftArgs {
+            nonce: 0,
+            metadata_src: MetadataSrc::DataHash(DataHashArgs {
+                meta_hash: [0; 32],
+                creator_shares: vec![50, 25, 25],
+                creator_verified: vec![true, false, false],
+                seller_fee_basis_points: 500,
+            }),
+            merkle_tree: &merkle_tree,
+            creator_accounts: &creator_accounts,
+        });
+
+        assert!(matches!(
+            result,
+            Err(error) if error == TensorError::DuplicateCreator.into()
+        ));
+    }
+
+    fn account_info(key: Pubkey, executable: bool) -> AccountInfo<'static> {
+        let mut account = crate::test_utils::account_info(key, Pubkey::default(), vec![]);
+        account.executable = executable;
+        account
+    }
+
+    #[test]
+    fn test_assert_cnft_programs() {
+        let log_wrapper = account_info(spl_noop::ID, true);
+        let compression_program = account_info(spl_account_compression::ID, true);
+        let bubblegum_program = account_info(mpl_bubblegum::ID, true);
+        let impostor = account_info(Pubkey::new_unique(), true);
+
+        assert!(
+            assert_cnft_programs(&log_wrapper, &compression_program, &bubblegum_program).is_ok()
+        );
+
+        for (log_wrapper, compression_program, bubblegum_program) in [
+            (&impostor, &compression_program, &bubblegum_program),
+            (&log_wrapper, &impostor, &bubblegum_program),
+            (&log_wrapper, &compression_program, &impostor),
+            // swapped programs
+            (&compression_program, &log_wrapper, &bubblegum_program),
+        ] {
+            assert_eq!(
+                assert_cnft_programs(log_wrapper, compression_program, bubblegum_program),
+                Err(ErrorCode::InvalidProgramId.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_transfer_cnft_new_leaf_owner() {
+        setup_syscall_stubs();
+
+        let account = account_info(Pubkey::new_unique(), false);
+        let log_wrapper = account_info(spl_noop::ID, true);
+        let compression_program = account_info(spl_account_compression::ID, true);
+        let bubblegum_program = account_info(mpl_bubblegum::ID, true);
+
+        let transfer = |new_leaf_owner: &AccountInfo<'static>| {
+            transfer_cnft(TransferArgs {
+                root: [0; 32],
+                nonce: 0,
+                index: 0,
+                data_hash: [0; 32],
+                creator_hash: [0; 32],
+                tree_authority: &account,
+                leaf_owner: &account,
+                leaf_delegate: &account,
+                new_leaf_owner,
+                merkle_tree: &account,
+                log_wrapper: &log_wrapper,
+                compression_program: &compression_program,
+                system_program: &account,
+                bubblegum_program: &bubblegum_program,
+                proof_accounts: &[],
+                signer: None,
+                signer_seeds: None,
+            })
+        };
+
+        // zero key destination
+        assert_eq!(
+            transfer(&account_info(Pubkey::default(), false)),
+            Err(TensorError::InvalidDestination.into())
+        );
+        assert!(take_invoked_instructions().is_empty());
+
+        // valid destination
+        let new_leaf_owner = account_info(Pubkey::new_unique(), false);
+        transfer(&new_leaf_owner).unwrap();
+
+        let invoked = take_invoked_instructions();
+        assert_eq!(invoked.len(), 1);
+        assert_eq!(invoked[0].program_id, mpl_bubblegum::ID);
+        assert!(invoked[0]
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == *new_leaf_owner.key));
+    }
+
+    #[test]
+    fn test_transfer_cnft_spoofed_programs() {
+        let account = account_info(Pubkey::new_unique(), false);
+        let impostor = account_info(Pubkey::new_unique(), true);
+        let compression_program = account_info(spl_account_compression::ID, true);
+        let bubblegum_program = account_info(mpl_bubblegum::ID, true);
+
+        let result = transfer_cnft(TransferArgs {
+            root: [0; 32],
+            nonce: 0,
+            index: 0,
+            data_hash: [0; 32],
+            creator_hash: [0; 32],
+            tree_authority: &account,
+            leaf_owner: &account,
+            leaf_delegate: &account,
+            new_leaf_owner: &account,
+            merkle_tree: &account,
+            log_wrapper: &impostor,
+            compression_program: &compression_program,
+            system_program: &account,
+            bubblegum_program: &bubblegum_program,
+            proof_accounts: &[],
+            signer: None,
+            signer_seeds: None,
+        });
+
+        assert_eq!(result, Err(ErrorCode::InvalidProgramId.into()));
+    }
+}
@@ -1,13 +1,21 @@
 #![allow(clippy::result_large_err)]
 use anchor_lang::{
     prelude::*,
-    solana_program::{program::invoke, pubkey::Pubkey, system_instruction, system_program},
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::{invoke, invoke_signed},
+        program_pack::Pack,
+        pubkey::Pubkey,
+        system_instruction, system_program,
+    },
 };
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::spl_token,
     token_2022::spl_token_2022,
-    token_interface::{TokenAccount, TokenInterface},
+    token_interface::{
+        spl_token_2022::extension::StateWithExtensions, Mint, TokenAccount, TokenInterface,
+    },
 };
 use mpl_token_metadata::types::TokenStandard;
 use std::slice::Iter;
@@ -23,8 +31,43 @@ pub const TNSR_DISCOUNT_BPS: u64 = 2500;
 pub const TAKER_FEE_BPS: u64 = 200;
 pub const MAKER_BROKER_PCT: u64 = 80; // Out of 100
 
+/// Decodes a base58 string into a `Pubkey` in a const context.
+///
+/// Invalid strings panic, which fails the compilation when used in a `const` declaration.
+/// The panic message identifies the problem: whitespace, a non-base58 character (`0`, `O`,
+/// `I` and `l` are excluded from the alphabet) or a string that does not decode to 32 bytes.
+///
+/// ```compile_fail
+/// # use anchor_lang::prelude::Pubkey;
+/// const KEY: Pubkey = tensor_toolbox::pubkey("11111111111111111111111111111111 ");
+/// ```
+///
+/// ```compile_fail
+/// # use anchor_lang::prelude::Pubkey;
+/// const KEY: Pubkey = tensor_toolbox::pubkey("1111111111111111111111111111111O");
+/// ```
 pub const fn pubkey(base58str: &str) -> Pubkey {
-    Pubkey::new_from_array(five8_const::decode_32_const(base58str))
+    let bytes = base58str.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                panic!("Invalid pubkey: base58 string contains whitespace")
+            }
+            b'0' | b'O' | b'I' | b'l' => {
+                panic!("Invalid pubkey: base58 string contains an invalid character (0, O, I or l)")
+            }
+            b'1'..=b'9' | b'A'..=b'Z' | b'a'..=b'z' => {}
+            _ => panic!("Invalid pubkey: base58 string contains a non-alphanumeric character"),
+        }
+        i += 1;
+    }
+
+    match five8_const::try_decode_32_const(base58str) {
+        Ok(bytes) => Pubkey::new_from_array(bytes),
+        Err(_) => panic!("Invalid pubkey: base58 string does not decode to 32 bytes"),
+    }
 }
 
 pub mod escrow {
@@ -58,6 +101,17 @@ pub mod price_lock {
     pub const TLOCK_SINGLETON: Pubkey = pubkey("CdXA5Vpg4hqvsmLSKC2cygnJVvsQTrDrrn428nAZQaKz");
 }
 
+/// Decodes an optional base58 string into an `Option<Pubkey>` in a const context.
+///
+/// The empty string maps to `None`; any other string is validated and decoded as in [`pubkey`].
+pub const fn pubkey_or_none(base58str: &str) -> Option<Pubkey> {
+    if base58str.is_empty() {
+        None
+    } else {
+        Some(pubkey(base58str))
+    }
+}
+
 /// Calculates fee vault shard from a given AccountInfo or Pubkey. Relies on the Anchor `Key` trait.
 #[macro_export]
 macro_rules! shard_num {
@@ -73,7 +127,31 @@ pub struct CalcFeesArgs {
     pub total_fee_bps: u64,
     pub broker_fee_pct: u64,
     pub maker_broker_pct: u64,
-    pub tnsr_discount: bool,
+    /// Discount on the total fee, in basis points of the fee (e.g. 2500 takes 25% off).
+    /// Discounts over 100% saturate to a zero fee.
+    pub discount_bps: Option<u64>,
+    /// Whether the trade is exempt from fees (e.g. privileged accounts or promotional
+    /// periods): all fee components are zero. Royalties are not affected.
+    pub is_fee_exempt: bool,
+}
+
+impl CalcFeesArgs {
+    /// Fee arguments with the standard TNSR discount applied.
+    pub fn with_tnsr_discount(
+        amount: u64,
+        total_fee_bps: u64,
+        broker_fee_pct: u64,
+        maker_broker_pct: u64,
+    ) -> Self {
+        Self {
+            amount,
+            total_fee_bps,
+            broker_fee_pct,
+            maker_broker_pct,
+            discount_bps: Some(TNSR_DISCOUNT_BPS),
+            is_fee_exempt: false,
+        }
+    }
 }
 
 /// Fees struct that holds the calculated fees.
@@ -88,53 +166,212 @@ pub struct Fees {
     pub taker_broker_fee: u64,
 }
 
-// Calculate fees for a given amount.
+// Calculate fees for a given amount, using the standard basis points denominator.
 pub fn calc_fees(args: CalcFeesArgs) -> Result<Fees> {
+    calc_fees_checked(args).map_err(Into::into)
+}
+
+/// Common interface over the fee calculation models used by the programs.
+pub trait FeeModel {
+    /// Computes the fees for the given amount.
+    fn compute(&self, amount: u64) -> Result<Fees>;
+}
+
+/// Legacy fee model, with a single taker broker.
+///
+/// The taker broker receives `taker_broker_pct` of the total fee and the protocol the
+/// remainder; there is no maker broker fee.
+pub struct LegacyFeeModel {
+    pub fee_bps: u16,
+    pub taker_broker_pct: u16,
+}
+
+impl FeeModel for LegacyFeeModel {
+    fn compute(&self, amount: u64) -> Result<Fees> {
+        let fee = unwrap_checked!({
+            (self.fee_bps as u64)
+                .checked_mul(amount)?
+                .checked_div(HUNDRED_PCT_BPS)
+        });
+
+        let taker_broker_fee = unwrap_checked!({
+            fee.checked_mul(self.taker_broker_pct as u64)?
+                .checked_div(HUNDRED_PCT)
+        });
+
+        let protocol_fee = unwrap_int!(fee.checked_sub(taker_broker_fee));
+
+        Ok(Fees {
+            taker_fee: fee,
+            protocol_fee,
+            maker_broker_fee: 0,
+            taker_broker_fee,
+        })
+    }
+}
+
+/// Fee configuration bundling the taker fee basis points and broker percentages, e.g. for
+/// per-market fee tiers.
+///
+/// The fee and discount are expressed in units of `bps_denominator`, which allows
+/// protocols to use a finer denomination (e.g. parts-per-million) than basis points.
+///
+/// This is also the fee model with the broker fees split between maker and taker brokers
+/// (see [`calc_fees`]). The default configuration uses the standard fee constants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeConfig {
+    pub total_fee_bps: u64,
+    pub broker_fee_pct: u64,
+    pub maker_broker_pct: u64,
+    /// Discount on the total fee, in units of `bps_denominator` of the fee.
+    pub discount_bps: Option<u64>,
+    /// Denominator of `total_fee_bps` and `discount_bps` (100%).
+    pub bps_denominator: u64,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            total_fee_bps: TAKER_FEE_BPS,
+            broker_fee_pct: BROKER_FEE_PCT,
+            maker_broker_pct: MAKER_BROKER_PCT,
+            discount_bps: None,
+            bps_denominator: HUNDRED_PCT_BPS,
+        }
+    }
+}
+
+impl FeeModel for FeeConfig {
+    fn compute(&self, amount: u64) -> Result<Fees> {
+        calc_fees_with_config(amount, self)
+    }
+}
+
+// Calculate fees for a given amount using a fee configuration.
+pub fn calc_fees_with_config(amount: u64, config: &FeeConfig) -> Result<Fees> {
+    calc_fees_with_config_checked(amount, config).map_err(Into::into)
+}
+
+/// Step of the fee calculation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeCalcStep {
+    /// Applying the discount to the total fee basis points.
+    Discount,
+    /// Total (taker) fee from the amount.
+    TotalFee,
+    /// Broker fees from the total fee.
+    BrokerFee,
+    /// Protocol fee: total fee minus the broker fees.
+    ProtocolFee,
+    /// Maker broker fee from the broker fees.
+    MakerBroker,
+    /// Taker broker fee: broker fees minus the maker broker fee.
+    TakerBroker,
+}
+
+/// Error of the fee calculation, identifying the step that overflowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeCalcError {
+    pub step: FeeCalcStep,
+}
+
+impl From<FeeCalcError> for anchor_lang::error::Error {
+    fn from(error: FeeCalcError) -> Self {
+        msg!("Fee calculation overflow: {:?}", error.step);
+        error!(VipersError::IntegerOverflow)
+    }
+}
+
+/// Calculates fees for a given amount, reporting which step of the calculation overflowed.
+pub fn calc_fees_checked(args: CalcFeesArgs) -> std::result::Result<Fees, FeeCalcError> {
     let CalcFeesArgs {
         amount,
         total_fee_bps,
         broker_fee_pct,
         maker_broker_pct,
-        tnsr_discount,
+        discount_bps,
+        is_fee_exempt,
     } = args;
 
-    // Apply the TNSR discount if enabled.
-    let total_fee_bps = if tnsr_discount {
-        unwrap_checked!({
+    if is_fee_exempt {
+        return Ok(Fees {
+            taker_fee: 0,
+            protocol_fee: 0,
+            maker_broker_fee: 0,
+            taker_broker_fee: 0,
+        });
+    }
+
+    calc_fees_with_config_checked(
+        amount,
+        &FeeConfig {
+            total_fee_bps,
+            broker_fee_pct,
+            maker_broker_pct,
+            discount_bps,
+            ..FeeConfig::default()
+        },
+    )
+}
+
+fn calc_fees_with_config_checked(
+    amount: u64,
+    config: &FeeConfig,
+) -> std::result::Result<Fees, FeeCalcError> {
+    let FeeConfig {
+        total_fee_bps,
+        broker_fee_pct,
+        maker_broker_pct,
+        discount_bps,
+        bps_denominator,
+    } = *config;
+
+    let checked = |value: Option<u64>, step| value.ok_or(FeeCalcError { step });
+
+    // Apply the discount, if any: discounts over 100% saturate to a zero fee.
+    let total_fee_bps = if let Some(discount_bps) = discount_bps {
+        checked(
             total_fee_bps
-                .checked_mul(HUNDRED_PCT_BPS - TNSR_DISCOUNT_BPS)?
-                .checked_div(HUNDRED_PCT_BPS)
-        })
+                .checked_mul(bps_denominator.saturating_sub(discount_bps))
+                .and_then(|bps| bps.checked_div(bps_denominator)),
+            FeeCalcStep::Discount,
+        )?
     } else {
         total_fee_bps
     };
 
     // Total fee is calculated from the passed in total_fee_bps and is protocol fee + broker fees.
-    let total_fee = unwrap_checked!({
-        (amount)
-            .checked_mul(total_fee_bps)?
-            .checked_div(HUNDRED_PCT_BPS)
-    });
+    let total_fee = checked(
+        amount
+            .checked_mul(total_fee_bps)
+            .and_then(|fee| fee.checked_div(bps_denominator)),
+        FeeCalcStep::TotalFee,
+    )?;
 
     // Broker fees are a percentage of the total fee.
-    let broker_fees = unwrap_checked!({
+    let broker_fees = checked(
         total_fee
-            .checked_mul(broker_fee_pct)?
-            .checked_div(HUNDRED_PCT)
-    });
+            .checked_mul(broker_fee_pct)
+            .and_then(|fee| fee.checked_div(HUNDRED_PCT)),
+        FeeCalcStep::BrokerFee,
+    )?;
 
     // Protocol fee is the remainder.
-    let protocol_fee = unwrap_checked!({ total_fee.checked_sub(broker_fees) });
+    let protocol_fee = checked(total_fee.checked_sub(broker_fees), FeeCalcStep::ProtocolFee)?;
 
     // Maker broker is a percentage of the total brokers fee.
-    let maker_broker_fee = unwrap_checked!({
+    let maker_broker_fee = checked(
         broker_fees
-            .checked_mul(maker_broker_pct)?
-            .checked_div(HUNDRED_PCT)
-    });
+            .checked_mul(maker_broker_pct)
+            .and_then(|fee| fee.checked_div(HUNDRED_PCT)),
+        FeeCalcStep::MakerBroker,
+    )?;
 
     // Remaining broker fee is the taker broker fee.
-    let taker_broker_fee = unwrap_int!(broker_fees.checked_sub(maker_broker_fee));
+    let taker_broker_fee = checked(
+        broker_fees.checked_sub(maker_broker_fee),
+        FeeCalcStep::TakerBroker,
+    )?;
 
     Ok(Fees {
         taker_fee: total_fee,
@@ -144,6 +381,166 @@ pub fn calc_fees(args: CalcFeesArgs) -> Result<Fees> {
     })
 }
 
+/// Fees struct that holds the calculated fees, the creators fee and the net proceeds of the seller.
+pub struct FeesWithRoyalties {
+    /// Taker fee is the total fee sans royalties: protocol fee + broker fees.
+    pub taker_fee: u64,
+    /// Protocol fee is the fee that goes to the protocol.
+    pub protocol_fee: u64,
+    /// Maker broker fee is the fee that goes to the maker broker.
+    pub maker_broker_fee: u64,
+    /// Taker broker fee is the fee that goes to the taker broker.
+    pub taker_broker_fee: u64,
+    /// Creators fee is the royalties paid to the creators.
+    pub creators_fee: u64,
+    /// Seller net is the remainder of the amount: amount - taker_fee - creators_fee.
+    pub seller_net: u64,
+}
+
+/// Calculates the fees and royalties for a given amount in one pass.
+///
+/// All fees are rounded down and the seller net is the remainder of the amount, so rounding
+/// always favours the seller and `taker_fee + creators_fee + seller_net == amount`.
+///
+/// Errors if the fees and royalties exceed the amount.
+pub fn calc_fees_with_royalties(
+    args: CalcFeesArgs,
+    seller_fee_basis_points: u16,
+    royalty_pct: Option<u16>,
+) -> Result<FeesWithRoyalties> {
+    let amount = args.amount;
+    let fees = calc_fees(args)?;
+
+    add_royalties(amount, fees, seller_fee_basis_points, royalty_pct)
+}
+
+/// Calculates the net proceeds of a seller for a sale: the amount minus the taker fee and the
+/// creators fee.
+///
+/// Errors if the fees and royalties exceed the sale amount.
+pub fn seller_net_proceeds(
+    amount: u64,
+    fee_config: &FeeConfig,
+    seller_fee_basis_points: u16,
+    royalty_pct: Option<u16>,
+) -> Result<u64> {
+    let fees = calc_fees_with_config(amount, fee_config)?;
+
+    Ok(add_royalties(amount, fees, seller_fee_basis_points, royalty_pct)?.seller_net)
+}
+
+/// Calculates the minimum sale price whose net proceeds (the amount minus the taker fee) cover
+/// the rent exemption of the accounts created at settlement.
+///
+/// Created accounts are assumed to be token accounts, the largest accounts usually created
+/// when settling a sale (e.g. creator and buyer ATAs). Errors if the taker fee is 100% or more,
+/// since no price can cover the rent.
+pub fn minimum_viable_price(fee_config: &FeeConfig, num_accounts_created: usize) -> Result<u64> {
+    let rent_per_account = Rent::get()?.minimum_balance(spl_token::state::Account::LEN);
+    let rent = unwrap_int!(rent_per_account.checked_mul(num_accounts_created as u64));
+
+    if rent == 0 {
+        return Ok(0);
+    }
+
+    // The taker fee on an amount equal to the denominator is the effective (discounted) fee.
+    let denominator = fee_config.bps_denominator;
+    let fee_bps = calc_fees_with_config(denominator, fee_config)?.taker_fee;
+
+    if fee_bps >= denominator {
+        msg!("Taker fee must be less than 100% to cover rent");
+        return Err(TensorError::ArithmeticError.into());
+    }
+
+    let net_proceeds = |price: u64| -> Result<u64> {
+        let fees = calc_fees_with_config(price, fee_config)?;
+        Ok(unwrap_int!(price.checked_sub(fees.taker_fee)))
+    };
+
+    // Estimate the price from `price * (1 - fee) >= rent`, then adjust it for the rounding of
+    // the fee, which is rounded down.
+    let estimate = (rent as u128 - 1) * denominator as u128 / (denominator - fee_bps) as u128 + 1;
+    let mut price = unwrap_opt!(u64::try_from(estimate).ok(), TensorError::ArithmeticError);
+
+    while net_proceeds(price)? < rent {
+        price = unwrap_int!(price.checked_add(1));
+    }
+    while price > 0 && net_proceeds(price - 1)? >= rent {
+        price -= 1;
+    }
+
+    Ok(price)
+}
+
+fn add_royalties(
+    amount: u64,
+    fees: Fees,
+    seller_fee_basis_points: u16,
+    royalty_pct: Option<u16>,
+) -> Result<FeesWithRoyalties> {
+    let Fees {
+        taker_fee,
+        protocol_fee,
+        maker_broker_fee,
+        taker_broker_fee,
+    } = fees;
+    let creators_fee = calc_creators_fee(seller_fee_basis_points, amount, royalty_pct)?;
+
+    let deductions = unwrap_int!(taker_fee.checked_add(creators_fee));
+    require!(deductions <= amount, TensorError::InsufficientBalance);
+
+    Ok(FeesWithRoyalties {
+        taker_fee,
+        protocol_fee,
+        maker_broker_fee,
+        taker_broker_fee,
+        creators_fee,
+        seller_net: amount - deductions,
+    })
+}
+
+/// Redirects the share of an absent broker to the protocol fee.
+///
+/// When the maker or taker broker account is not present, their share of the broker fees is
+/// added to the protocol fee instead of being computed into a payout that cannot be made.
+/// The total (taker) fee is unchanged.
+pub fn redistribute_absent_broker_fees(
+    fees: Fees,
+    maker_broker: Option<&Pubkey>,
+    taker_broker: Option<&Pubkey>,
+) -> Result<Fees> {
+    let Fees {
+        taker_fee,
+        mut protocol_fee,
+        mut maker_broker_fee,
+        mut taker_broker_fee,
+    } = fees;
+
+    if maker_broker.is_none() {
+        protocol_fee = unwrap_int!(protocol_fee.checked_add(maker_broker_fee));
+        maker_broker_fee = 0;
+    }
+
+    if taker_broker.is_none() {
+        protocol_fee = unwrap_int!(protocol_fee.checked_add(taker_broker_fee));
+        taker_broker_fee = 0;
+    }
+
+    Ok(Fees {
+        taker_fee,
+        protocol_fee,
+        maker_broker_fee,
+        taker_broker_fee,
+    })
+}
+
+/// Asserts that an asset is not being transferred to its current owner.
+pub fn assert_not_self_transfer(source_owner: &Pubkey, destination_owner: &Pubkey) -> Result<()> {
+    require_keys_neq!(*source_owner, *destination_owner, TensorError::SelfTransfer);
+
+    Ok(())
+}
+
 pub fn is_royalty_enforced(token_standard: Option<TokenStandard>) -> bool {
     matches!(
         token_standard,
@@ -152,24 +549,38 @@ pub fn is_royalty_enforced(token_standard: Option<TokenStandard>) -> bool {
     )
 }
 
-pub fn calc_creators_fee(
+/// Calculates the effective creators fee rate in basis points, after applying the optional
+/// `royalty_pct` (0-100) to the seller fee basis points.
+///
+/// This is the rate charged by [`calc_creators_fee`]: when `royalty_pct` is `None`, no
+/// optional royalties are paid and the effective rate is 0.
+pub fn effective_royalty_bps(
     seller_fee_basis_points: u16,
-    amount: u64,
     royalty_pct: Option<u16>,
-) -> Result<u64> {
-    let creator_fee_bps = if let Some(royalty_pct) = royalty_pct {
+) -> Result<u16> {
+    if let Some(royalty_pct) = royalty_pct {
         require!(royalty_pct <= 100, TensorError::BadRoyaltiesPct);
 
         // If optional passed, pay optional royalties
-        unwrap_checked!({
+        let bps = unwrap_checked!({
             (seller_fee_basis_points as u64)
                 .checked_mul(royalty_pct as u64)?
                 .checked_div(100_u64)
-        })
+        });
+        // Cannot exceed the seller fee basis points since royalty_pct <= 100.
+        Ok(bps as u16)
     } else {
         // Else pay 0
-        0_u64
-    };
+        Ok(0)
+    }
+}
+
+pub fn calc_creators_fee(
+    seller_fee_basis_points: u16,
+    amount: u64,
+    royalty_pct: Option<u16>,
+) -> Result<u64> {
+    let creator_fee_bps = effective_royalty_bps(seller_fee_basis_points, royalty_pct)? as u64;
     let fee = unwrap_checked!({
         creator_fee_bps
             .checked_mul(amount)?
@@ -179,6 +590,24 @@ pub fn calc_creators_fee(
     Ok(fee)
 }
 
+/// Adds lamports to an account, erroring on overflow.
+pub fn credit_lamports(account: &AccountInfo, amount: u64) -> Result<()> {
+    let mut lamports = account.try_borrow_mut_lamports()?;
+    **lamports = unwrap_int!(lamports.checked_add(amount));
+
+    Ok(())
+}
+
+/// Removes lamports from an account, erroring on underflow.
+///
+/// The account must be owned by the executing program for the debit to be accepted by the runtime.
+pub fn debit_lamports(account: &AccountInfo, amount: u64) -> Result<()> {
+    let mut lamports = account.try_borrow_mut_lamports()?;
+    **lamports = unwrap_int!(lamports.checked_sub(amount));
+
+    Ok(())
+}
+
 /// Transfers all lamports from a PDA (except for rent) to a destination account.
 pub fn transfer_all_lamports_from_pda<'info>(
     from_pda: &AccountInfo<'info>,
@@ -190,6 +619,62 @@ pub fn transfer_all_lamports_from_pda<'info>(
     transfer_lamports_from_pda(from_pda, to, to_move)
 }
 
+/// Transfers the full balance of a token account to another token account, returning the
+/// amount transferred.
+///
+/// Supports both Token and Token 2022 programs. For Token 2022 mints with the transfer fee
+/// extension, `to_ata` receives the transferred amount minus the fee, which is withheld in
+/// the destination account. Mints with a transfer hook are not supported, since the hook
+/// accounts are not passed to the transfer.
+///
+/// Errors if `from_ata` is not a token account of `mint`.
+pub fn drain_token_account<'info>(
+    from_ata: &AccountInfo<'info>,
+    to_ata: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: Option<&[&[&[u8]]]>,
+) -> Result<u64> {
+    require!(
+        SPL_TOKEN_IDS.contains(token_program.key),
+        ErrorCode::InvalidProgramId
+    );
+
+    let from = TokenAccount::try_deserialize(&mut &from_ata.try_borrow_data()?[..])?;
+    require!(from.mint == mint.key(), TensorError::InvalidMint);
+
+    let amount = from.amount;
+
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    let decimals = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?.decimals;
+
+    let ctx = CpiContext::new(
+        token_program.clone(),
+        anchor_spl::token_interface::TransferChecked {
+            from: from_ata.clone(),
+            mint: mint.clone(),
+            to: to_ata.clone(),
+            authority: authority.clone(),
+        },
+    );
+
+    if let Some(signer_seeds) = signer_seeds {
+        anchor_spl::token_interface::transfer_checked(
+            ctx.with_signer(signer_seeds),
+            amount,
+            decimals,
+        )?;
+    } else {
+        anchor_spl::token_interface::transfer_checked(ctx, amount, decimals)?;
+    }
+
+    Ok(amount)
+}
+
 /// Transfers specified lamports from a PDA to a destination account.
 /// Throws an error if less than rent remains in the PDA.
 pub fn transfer_lamports_from_pda<'info>(
@@ -205,12 +690,8 @@ pub fn transfer_lamports_from_pda<'info>(
         TensorError::InsufficientBalance
     );
 
-    **from_pda.try_borrow_mut_lamports()? = remaining_pda_lamports;
-
-    let new_to = unwrap_int!(to.lamports.borrow().checked_add(lamports));
-    **to.lamports.borrow_mut() = new_to;
-
-    Ok(())
+    debit_lamports(from_pda, lamports)?;
+    credit_lamports(to, lamports)
 }
 
 pub struct FromExternal<'b, 'info> {
@@ -282,6 +763,62 @@ pub enum CreatorFeeMode<'a, 'info> {
     },
 }
 
+/// Payment mode of a creators fee distribution, without the accounts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeMode {
+    Sol,
+    Spl,
+}
+
+impl<'a, 'info> CreatorFeeMode<'a, 'info> {
+    pub fn fee_mode(&self) -> FeeMode {
+        match self {
+            CreatorFeeMode::Sol { .. } => FeeMode::Sol,
+            CreatorFeeMode::Spl { .. } => FeeMode::Spl,
+        }
+    }
+}
+
+/// Base compute units of a creators fee distribution, independent of the number of creators.
+pub const CREATORS_FEE_BASE_CU: u32 = 1_000;
+/// Compute units per creator paid in SOL: rent sysvar read and a system program transfer CPI.
+pub const CREATORS_FEE_SOL_CU: u32 = 5_000;
+/// Compute units per creator paid in SPL tokens: ATA creation CPI (worst case, when the creator
+/// does not have one), token account validation and a token transfer CPI.
+pub const CREATORS_FEE_SPL_CU: u32 = 40_000;
+
+/// Estimates the compute units consumed by `transfer_creators_fee`.
+///
+/// This is a conservative heuristic that assumes the worst case for every creator: each creator
+/// is paid through a CPI and, in SPL mode, each creator ATA needs to be created. It scales linearly
+/// with the number of creators and is intended to help callers set compute budgets.
+pub fn estimate_creators_fee_cu(num_creators: usize, mode: FeeMode) -> u32 {
+    let per_creator = match mode {
+        FeeMode::Sol => CREATORS_FEE_SOL_CU,
+        FeeMode::Spl => CREATORS_FEE_SPL_CU,
+    };
+    let num_creators = u32::try_from(num_creators).unwrap_or(u32::MAX);
+
+    CREATORS_FEE_BASE_CU.saturating_add(per_creator.saturating_mul(num_creators))
+}
+
+/// Computes the amount each creator receives from `creator_fee`, without moving any lamports.
+///
+/// Amounts are truncated the same way as in `transfer_creators_fee` (`share * fee / 100`), so
+/// the sum can be less than `creator_fee` by the rounding dust. Note that in SOL mode
+/// `transfer_creators_fee` skips creators whose account would not be rent exempt after the
+/// payment; those amounts are still listed here, since skipping depends on account state.
+pub fn compute_creator_distribution(creators: &[TCreator], creator_fee: u64) -> Vec<(Pubkey, u64)> {
+    creators
+        .iter()
+        .map(|creator| {
+            // u128 to avoid overflows; the result fits in a u64 for shares up to 100
+            let amount = creator.share as u128 * creator_fee as u128 / 100;
+            (creator.address, u64::try_from(amount).unwrap_or(u64::MAX))
+        })
+        .collect()
+}
+
 pub fn transfer_creators_fee<'a, 'info>(
     //using TCreator here so that this fn is agnostic to normal NFTs and cNFTs
     creators: &'a Vec<TCreator>,
@@ -289,10 +826,82 @@ pub fn transfer_creators_fee<'a, 'info>(
     creator_fee: u64,
     // put not-in-common args in an enum so the invoker doesn't require it
     mode: &'a CreatorFeeMode<'a, 'info>,
+) -> Result<u64> {
+    transfer_creators_fee_inner(creators, creator_accounts, creator_fee, mode, None, false)
+}
+
+/// Variant of `transfer_creators_fee` that routes the shares of skipped creators to a
+/// `fallback` account (e.g. a protocol treasury) instead of leaving them in the source.
+///
+/// Accounting:
+/// - creators are paid their share as in `transfer_creators_fee`
+/// - in SOL mode, a creator is skipped when their account would not be rent exempt after the
+///   payment; the shares of skipped creators are added up and sent to `fallback` in a single
+///   transfer after all creators are processed
+/// - SPL mode never skips creators, so `fallback` is not used
+/// - rounding dust is never sent to `fallback` and stays in the source
+///
+/// The returned amount includes what was sent to `fallback`. When `fallback` is `None`, this
+/// behaves exactly like `transfer_creators_fee`. The `fallback` account should be rent exempt,
+/// since it is not checked before being paid.
+pub fn transfer_creators_fee_with_fallback<'a, 'info>(
+    creators: &'a Vec<TCreator>,
+    creator_accounts: &mut Iter<AccountInfo<'info>>,
+    creator_fee: u64,
+    mode: &'a CreatorFeeMode<'a, 'info>,
+    fallback: Option<&AccountInfo<'info>>,
+) -> Result<u64> {
+    transfer_creators_fee_inner(
+        creators,
+        creator_accounts,
+        creator_fee,
+        mode,
+        fallback,
+        false,
+    )
+}
+
+/// Variant of `transfer_creators_fee_with_fallback` with a compact account layout, where
+/// creators that receive nothing do not have an ATA account.
+///
+/// A creator receives nothing when their share of `creator_fee` truncates to zero, which is
+/// always the case for a 0% share (see `compute_creator_distribution` to predict it). In SPL
+/// mode, callers must pass the creator account but *not* the ATA account for these creators;
+/// in SOL mode the layout is the same as `transfer_creators_fee`, since creators do not have
+/// an ATA account.
+///
+/// `transfer_creators_fee` and `transfer_creators_fee_with_fallback` also skip creators that
+/// receive nothing, but still expect (and consume) their ATA account in SPL mode.
+pub fn transfer_creators_fee_compact<'a, 'info>(
+    creators: &'a Vec<TCreator>,
+    creator_accounts: &mut Iter<AccountInfo<'info>>,
+    creator_fee: u64,
+    mode: &'a CreatorFeeMode<'a, 'info>,
+    fallback: Option<&AccountInfo<'info>>,
+) -> Result<u64> {
+    transfer_creators_fee_inner(
+        creators,
+        creator_accounts,
+        creator_fee,
+        mode,
+        fallback,
+        true,
+    )
+}
+
+fn transfer_creators_fee_inner<'a, 'info>(
+    creators: &'a Vec<TCreator>,
+    creator_accounts: &mut Iter<AccountInfo<'info>>,
+    creator_fee: u64,
+    mode: &'a CreatorFeeMode<'a, 'info>,
+    fallback: Option<&AccountInfo<'info>>,
+    // whether the ATA account of creators that receive nothing is omitted (SPL mode)
+    compact: bool,
 ) -> Result<u64> {
     // Send royalties: taken from AH's calculation:
     // https://github.com/metaplex-foundation/metaplex-program-library/blob/2320b30ec91b729b153f0c0fe719f96d325b2358/auction-house/program/src/utils.rs#L366-L471
     let mut remaining_fee = creator_fee;
+    let mut skipped_fee = 0u64;
     for creator in creators {
         let current_creator_info = next_account_info(creator_accounts)?;
 
@@ -304,6 +913,14 @@ pub fn transfer_creators_fee<'a, 'info>(
         let pct = creator.share as u64;
         let creator_fee = unwrap_checked!({ pct.checked_mul(creator_fee)?.checked_div(100) });
 
+        // Nothing to pay: skip the rent check and the ATA creation.
+        if creator_fee == 0 {
+            if let (CreatorFeeMode::Spl { .. }, false) = (mode, compact) {
+                next_account_info(creator_accounts)?;
+            }
+            continue;
+        }
+
         let current_creator_ta_info = match mode {
             CreatorFeeMode::Sol { from: _ } => {
                 // Prevents InsufficientFundsForRent, where creator acc doesn't have enough fee
@@ -311,6 +928,7 @@ pub fn transfer_creators_fee<'a, 'info>(
                 let rent = Rent::get()?.minimum_balance(current_creator_info.data_len());
                 if unwrap_int!(current_creator_info.lamports().checked_add(creator_fee)) < rent {
                     //skip current creator, we can't pay them
+                    skipped_fee = unwrap_int!(skipped_fee.checked_add(creator_fee));
                     continue;
                 }
                 None
@@ -333,26 +951,9 @@ pub fn transfer_creators_fee<'a, 'info>(
 
         if creator_fee > 0 {
             match mode {
-                CreatorFeeMode::Sol { from } => match from {
-                    FromAcc::Pda(from_pda) => {
-                        transfer_lamports_from_pda(from_pda, current_creator_info, creator_fee)?;
-                    }
-                    FromAcc::External(from_ext) => {
-                        let FromExternal { from, sys_prog } = from_ext;
-                        invoke(
-                            &system_instruction::transfer(
-                                from.key,
-                                current_creator_info.key,
-                                creator_fee,
-                            ),
-                            &[
-                                (*from).clone(),
-                                current_creator_info.clone(),
-                                (*sys_prog).clone(),
-                            ],
-                        )?;
-                    }
-                },
+                CreatorFeeMode::Sol { from } => {
+                    transfer_sol_fee(from, current_creator_info, creator_fee)?;
+                }
 
                 CreatorFeeMode::Spl {
                     associated_token_program,
@@ -374,6 +975,10 @@ pub fn transfer_creators_fee<'a, 'info>(
                     if creator_ta_info.data_is_empty()
                         && creator_ta_info.owner == &system_program::ID
                     {
+                        assert_ata_program_matches(
+                            associated_token_program.key,
+                            token_program.key,
+                        )?;
                         anchor_spl::associated_token::create(CpiContext::new(
                             associated_token_program.to_account_info(),
                             anchor_spl::associated_token::Create {
@@ -441,42 +1046,186 @@ pub fn transfer_creators_fee<'a, 'info>(
         }
     }
 
+    // Skipped shares only happen in SOL mode.
+    if let (Some(fallback), CreatorFeeMode::Sol { from }) = (fallback, mode) {
+        if skipped_fee > 0 {
+            transfer_sol_fee(from, fallback, skipped_fee)?;
+            remaining_fee = unwrap_int!(remaining_fee.checked_sub(skipped_fee));
+        }
+    }
+
     // Return the amount that was sent (minus any dust).
     Ok(unwrap_int!(creator_fee.checked_sub(remaining_fee)))
 }
 
-// NOT: https://github.com/coral-xyz/sealevel-attacks/blob/master/programs/9-closing-accounts/secure/src/lib.rs
-// Instead: https://github.com/coral-xyz/anchor/blob/b7bada148cead931bc3bdae7e9a641e9be66e6a6/lang/src/common.rs#L6
-pub fn close_account(
-    pda_to_close: &mut AccountInfo,
-    sol_destination: &mut AccountInfo,
+/// Transfers a SOL fee from either a PDA or an external account.
+fn transfer_sol_fee<'info>(
+    from: &FromAcc<'_, 'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
 ) -> Result<()> {
-    // Transfer tokens from the account to the sol_destination.
-    let dest_starting_lamports = sol_destination.lamports();
-    **sol_destination.lamports.borrow_mut() =
-        unwrap_int!(dest_starting_lamports.checked_add(pda_to_close.lamports()));
-    **pda_to_close.lamports.borrow_mut() = 0;
-
-    pda_to_close.assign(&system_program::ID);
-    pda_to_close.realloc(0, false).map_err(Into::into)
+    match from {
+        FromAcc::Pda(from_pda) => transfer_lamports_from_pda(from_pda, to, amount),
+        FromAcc::External(from_ext) => {
+            let FromExternal { from, sys_prog } = from_ext;
+            invoke(
+                &system_instruction::transfer(from.key, to.key, amount),
+                &[(*from).clone(), to.clone(), (*sys_prog).clone()],
+            )
+            .map_err(Into::into)
+        }
+    }
+}
+
+/// Slice-based variant of `transfer_creators_fee`.
+///
+/// Creator accounts are consumed from the front of `creator_accounts` (one account per creator
+/// in SOL mode, two in SPL mode) and the unconsumed tail is returned along with the amount sent,
+/// so callers packing several account groups into the remaining accounts can validate the rest
+/// of the layout.
+pub fn transfer_creators_fee_from_slice<'a, 'b, 'info>(
+    creators: &'a Vec<TCreator>,
+    creator_accounts: &'b [AccountInfo<'info>],
+    creator_fee: u64,
+    mode: &'a CreatorFeeMode<'a, 'info>,
+) -> Result<(u64, &'b [AccountInfo<'info>])> {
+    let mut accounts = creator_accounts.iter();
+    let sent = transfer_creators_fee(creators, &mut accounts, creator_fee, mode)?;
+
+    Ok((sent, accounts.as_slice()))
+}
+
+/// Asserts that the creators fee distributed (e.g. returned by `transfer_creators_fee`) covers
+/// the expected fee, allowing a shortfall of up to `tolerance` (e.g. the rounding dust).
+///
+/// Strict marketplaces can use this to refuse sales where creators were skipped.
+pub fn assert_royalties_paid(distributed: u64, expected: u64, tolerance: u64) -> Result<()> {
+    let shortfall = expected.saturating_sub(distributed);
+
+    if shortfall > tolerance {
+        msg!(
+            "Royalties shortfall: distributed {} of {} (tolerance {})",
+            distributed,
+            expected,
+            tolerance
+        );
+        return Err(TensorError::RoyaltiesNotPaid.into());
+    }
+
+    Ok(())
+}
+
+/// Partitions the remaining accounts into consecutive groups of the given sizes.
+///
+/// Useful when several variable-length account groups (e.g. creator accounts and proof
+/// accounts) are packed into the remaining accounts. Errors if the number of accounts is not
+/// exactly the sum of the group sizes.
+pub fn split_remaining_accounts<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    group_sizes: &[usize],
+) -> Result<Vec<&'a [AccountInfo<'info>]>> {
+    let expected = unwrap_opt!(
+        group_sizes
+            .iter()
+            .try_fold(0usize, |total, size| total.checked_add(*size)),
+        TensorError::ArithmeticError
+    );
+
+    if accounts.len() != expected {
+        msg!(
+            "Remaining accounts mismatch: expected {}, got {}",
+            expected,
+            accounts.len()
+        );
+        return Err(TensorError::RemainingAccountsMismatch.into());
+    }
+
+    let mut remaining = accounts;
+
+    Ok(group_sizes
+        .iter()
+        .map(|size| {
+            let (group, rest) = remaining.split_at(*size);
+            remaining = rest;
+            group
+        })
+        .collect())
+}
+
+// NOT: https://github.com/coral-xyz/sealevel-attacks/blob/master/programs/9-closing-accounts/secure/src/lib.rs
+// Instead: https://github.com/coral-xyz/anchor/blob/b7bada148cead931bc3bdae7e9a641e9be66e6a6/lang/src/common.rs#L6
+pub fn close_account(
+    pda_to_close: &mut AccountInfo,
+    sol_destination: &mut AccountInfo,
+) -> Result<()> {
+    close_account_metered(pda_to_close, sol_destination).map(|_| ())
+}
+
+/// Closes the account as [`close_account`], returning the lamports (rent) reclaimed by the
+/// destination, e.g. to log or reconcile rent reclamation.
+pub fn close_account_metered(
+    pda_to_close: &mut AccountInfo,
+    sol_destination: &mut AccountInfo,
+) -> Result<u64> {
+    let reclaimed = pda_to_close.lamports();
+
+    // Transfer tokens from the account to the sol_destination.
+    credit_lamports(sol_destination, reclaimed)?;
+    **pda_to_close.lamports.borrow_mut() = 0;
+
+    pda_to_close.assign(&system_program::ID);
+    pda_to_close.realloc(0, false)?;
+
+    Ok(reclaimed)
+}
+
+/// Path taken by `transfer_lamports` to move lamports between accounts.
+///
+/// The path depends only on the `from` account:
+///
+/// | from      | to        | path             |
+/// |-----------|-----------|------------------|
+/// | system    | system    | `SystemTransfer` |
+/// | system    | PDA       | `SystemTransfer` |
+/// | PDA       | system    | `Direct`         |
+/// | PDA       | PDA       | `Direct`         |
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LamportsTransferPath {
+    /// `from` is a system account without data: lamports are moved with a system program
+    /// transfer CPI, which can credit any writable account, including a PDA with data.
+    SystemTransfer,
+    /// `from` is a program-owned account (PDA): lamports are debited and credited directly,
+    /// making sure the PDA remains rent exempt.
+    Direct,
+}
+
+/// Returns the path `transfer_lamports` takes when transferring from the given account.
+pub fn lamports_transfer_path(from: &AccountInfo) -> LamportsTransferPath {
+    if from.data_is_empty() && from.owner == &system_program::ID {
+        LamportsTransferPath::SystemTransfer
+    } else {
+        LamportsTransferPath::Direct
+    }
 }
 
 /// Transfers lamports from one account to another, handling the cases where the account
 /// is either a PDA or a system account.
+///
+/// The destination can be either a system account or a PDA with data; see
+/// [`LamportsTransferPath`] for the path taken on each case.
 pub fn transfer_lamports<'info>(
     from: &AccountInfo<'info>,
     to: &AccountInfo<'info>,
     lamports: u64,
 ) -> Result<()> {
-    // if the from account is empty, we can use the system program to transfer
-    if from.data_is_empty() && from.owner == &system_program::ID {
-        invoke(
+    match lamports_transfer_path(from) {
+        // if the from account is empty, we can use the system program to transfer
+        LamportsTransferPath::SystemTransfer => invoke(
             &system_instruction::transfer(from.key, to.key, lamports),
             &[from.clone(), to.clone()],
         )
-        .map_err(Into::into)
-    } else {
-        transfer_lamports_from_pda(from, to, lamports)
+        .map_err(Into::into),
+        LamportsTransferPath::Direct => transfer_lamports_from_pda(from, to, lamports),
     }
 }
 
@@ -503,17 +1252,131 @@ pub fn transfer_lamports_checked<'info, 'b>(
     }
 }
 
+/// Transfers lamports, failing if the `to` account would not be rent exempt.
+///
+/// Same as [`transfer_lamports_checked`], but returns a `WouldNotBeRentExempt` error instead of
+/// skipping the transfer, for flows where the lamports must not be silently left behind.
+pub fn transfer_lamports_or_err<'info, 'b>(
+    from: &'b AccountInfo<'info>,
+    to: &'b AccountInfo<'info>,
+    lamports: u64,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(to.data_len());
+    if unwrap_int!(to.lamports().checked_add(lamports)) < rent {
+        msg!(
+            "Cannot transfer {} lamports to {}: account would not be rent exempt",
+            lamports,
+            to.key
+        );
+        return Err(TensorError::WouldNotBeRentExempt.into());
+    }
+
+    transfer_lamports(from, to, lamports)
+}
+
+/// Owned signer seeds, for PDAs whose seeds (e.g. bumps) are computed at runtime.
+///
+/// The transfer helpers take the borrowed `&[&[&[u8]]]` form, which is produced by
+/// [`SignerSeeds::with_signer_seeds`]:
+///
+/// ```ignore
+/// let seeds = SignerSeeds::new(&[b"escrow", owner.as_ref()], bump);
+/// seeds.with_signer_seeds(|signer_seeds| transfer(args, Some(signer_seeds)))?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SignerSeeds(Vec<Vec<Vec<u8>>>);
+
+impl SignerSeeds {
+    /// Creates the signer seeds of a single PDA, appending the bump to the seeds.
+    pub fn new(seeds: &[&[u8]], bump: u8) -> Self {
+        let mut signer_seeds = Self::default();
+        signer_seeds.add_signer(seeds, bump);
+        signer_seeds
+    }
+
+    /// Adds the seeds of another PDA signer, appending the bump to the seeds.
+    pub fn add_signer(&mut self, seeds: &[&[u8]], bump: u8) -> &mut Self {
+        let mut signer = seeds.iter().map(|seed| seed.to_vec()).collect::<Vec<_>>();
+        signer.push(vec![bump]);
+        self.0.push(signer);
+        self
+    }
+
+    /// Calls `f` with the borrowed form of the signer seeds.
+    pub fn with_signer_seeds<R>(&self, f: impl FnOnce(&[&[&[u8]]]) -> R) -> R {
+        let signers = self
+            .0
+            .iter()
+            .map(|signer| signer.iter().map(Vec::as_slice).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let signer_seeds = signers.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        f(&signer_seeds)
+    }
+}
+
+/// Invokes an instruction on the program account, asserting that the program account
+/// matches the expected program id.
+///
+/// If `signer_seeds` are passed, uses `invoke_signed()` instead of `invoke()`.
+pub fn cpi_invoke<'info>(
+    program: &AccountInfo<'info>,
+    expected_id: &Pubkey,
+    accounts: Vec<AccountMeta>,
+    data: Vec<u8>,
+    account_infos: &[AccountInfo<'info>],
+    signer_seeds: Option<&[&[&[u8]]]>,
+) -> Result<()> {
+    require_keys_eq!(*program.key, *expected_id, ErrorCode::InvalidProgramId);
+
+    let instruction = Instruction {
+        program_id: *expected_id,
+        accounts,
+        data,
+    };
+
+    if let Some(signer_seeds) = signer_seeds {
+        invoke_signed(&instruction, account_infos, signer_seeds)
+    } else {
+        invoke(&instruction, account_infos)
+    }
+    .map_err(Into::into)
+}
+
+/// Asserts that the associated token program can create accounts for the token program.
+///
+/// The associated token program supports both the legacy and Token 2022 programs, so the
+/// valid pairings are the associated token program with either of the [`SPL_TOKEN_IDS`].
+pub fn assert_ata_program_matches(ata_program: &Pubkey, token_program: &Pubkey) -> Result<()> {
+    require_keys_eq!(
+        *ata_program,
+        anchor_spl::associated_token::ID,
+        ErrorCode::InvalidProgramId
+    );
+
+    if !SPL_TOKEN_IDS.contains(token_program) {
+        msg!(
+            "Token program {} is not supported by the associated token program",
+            token_program
+        );
+        return Err(ErrorCode::InvalidProgramId.into());
+    }
+
+    Ok(())
+}
+
 /// Asserts that the account is a valid fee account: either one of the program singletons or the fee vault.
 pub fn assert_fee_account(fee_vault_info: &AccountInfo, state_info: &AccountInfo) -> Result<()> {
-    let expected_fee_vault = Pubkey::find_program_address(
-        &[
-            b"fee_vault",
-            // Use the last byte of the mint as the fee shard number
-            shard_num!(state_info),
-        ],
-        &fees::ID,
-    )
-    .0;
+    // Use the last byte of the state account as the fee shard number
+    assert_fee_account_with_shard(fee_vault_info, state_info.key().as_ref()[31])
+}
+
+/// Asserts that the account is a valid fee account for an explicit fee shard number: either one
+/// of the program singletons or the fee vault of the shard.
+///
+/// Useful when the shard is not derived from the state account, e.g. from the mint.
+pub fn assert_fee_account_with_shard(fee_vault_info: &AccountInfo, shard: u8) -> Result<()> {
+    let expected_fee_vault = Pubkey::find_program_address(&[b"fee_vault", &[shard]], &fees::ID).0;
 
     require!(
         fee_vault_info.key == &expected_fee_vault
@@ -524,9 +1387,112 @@ pub fn assert_fee_account(fee_vault_info: &AccountInfo, state_info: &AccountInfo
     Ok(())
 }
 
+/// Asserts that the mint has no freeze authority, so its tokens cannot be frozen while in custody.
+pub fn assert_no_freeze_authority(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    if mint.freeze_authority.is_some() {
+        msg!("Mint {} has a freeze authority", mint.key());
+        return Err(TensorError::InvalidMint.into());
+    }
+
+    Ok(())
+}
+
+/// Asserts that the (legacy or Token 2022) mint account has no freeze authority.
+///
+/// The mint account must be owned by one of the [`SPL_TOKEN_IDS`].
+pub fn assert_no_freeze_authority_info(mint_info: &AccountInfo) -> Result<()> {
+    if !SPL_TOKEN_IDS.contains(mint_info.owner) {
+        msg!("Mint must be owned by a token program");
+        return Err(ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+
+    let mint_data = &mint_info.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(mint_data)?;
+
+    if mint.base.freeze_authority.is_some() {
+        msg!("Mint {} has a freeze authority", mint_info.key);
+        return Err(TensorError::InvalidMint.into());
+    }
+
+    Ok(())
+}
+
+/// Checks whether the mint is the wrapped SOL (native) mint of either token program.
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+/// Asserts that the mint is not the wrapped SOL mint, e.g. when native SOL is handled
+/// separately from SPL currencies and wSOL would be double-counted.
+pub fn assert_not_native_mint(mint: &Pubkey) -> Result<()> {
+    if is_native_mint(mint) {
+        msg!("Wrapped SOL mint {} is not allowed", mint);
+        return Err(TensorError::InvalidMint.into());
+    }
+
+    Ok(())
+}
+
+/// Asserts that the mint is the wrapped SOL mint.
+pub fn assert_native_mint(mint: &Pubkey) -> Result<()> {
+    if !is_native_mint(mint) {
+        msg!("Mint {} is not the wrapped SOL mint", mint);
+        return Err(TensorError::InvalidMint.into());
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    use anchor_lang::solana_program::{program_option::COption, program_pack::Pack};
+
     use super::*;
+    use crate::test_utils::account_info;
+
+    fn mint_info(token_program: Pubkey, freeze_authority: Option<Pubkey>) -> AccountInfo<'static> {
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            supply: 1,
+            is_initialized: true,
+            freeze_authority: freeze_authority.into(),
+            mint_authority: COption::None,
+            decimals: 0,
+        }
+        .pack_into_slice(&mut data);
+
+        account_info(Pubkey::new_unique(), token_program, data)
+    }
+
+    #[test]
+    fn test_assert_no_freeze_authority() {
+        for token_program in [spl_token::ID, spl_token_2022::ID] {
+            let mint = mint_info(token_program, None);
+            assert!(assert_no_freeze_authority_info(&mint).is_ok());
+            let mint: InterfaceAccount<Mint> =
+                InterfaceAccount::try_from(Box::leak(Box::new(mint))).unwrap();
+            assert!(assert_no_freeze_authority(&mint).is_ok());
+
+            let mint = mint_info(token_program, Some(Pubkey::new_unique()));
+            assert_eq!(
+                assert_no_freeze_authority_info(&mint),
+                Err(TensorError::InvalidMint.into())
+            );
+            let mint: InterfaceAccount<Mint> =
+                InterfaceAccount::try_from(Box::leak(Box::new(mint))).unwrap();
+            assert_eq!(
+                assert_no_freeze_authority(&mint),
+                Err(TensorError::InvalidMint.into())
+            );
+        }
+
+        // spoofed mint owned by another program
+        let mint = mint_info(Pubkey::new_unique(), None);
+        assert_eq!(
+            assert_no_freeze_authority_info(&mint),
+            Err(ErrorCode::AccountOwnedByWrongProgram.into())
+        );
+    }
 
     #[test]
     fn pubkey_constant() {
@@ -543,6 +1509,1227 @@ mod tests {
         );
     }
 
+    fn test_fees() -> Fees {
+        calc_fees(CalcFeesArgs {
+            amount: 1_000_000,
+            total_fee_bps: 200,
+            broker_fee_pct: 50,
+            maker_broker_pct: 80,
+            discount_bps: None,
+            is_fee_exempt: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_calc_fees_fee_exempt() {
+        let args = |is_fee_exempt| CalcFeesArgs {
+            amount: 1_000_000,
+            total_fee_bps: 200,
+            broker_fee_pct: 50,
+            maker_broker_pct: 80,
+            discount_bps: None,
+            is_fee_exempt,
+        };
+
+        let fees = calc_fees(args(true)).unwrap();
+        assert_eq!(fees.taker_fee, 0);
+        assert_eq!(fees.protocol_fee, 0);
+        assert_eq!(fees.maker_broker_fee, 0);
+        assert_eq!(fees.taker_broker_fee, 0);
+        assert_eq!(calc_fees(args(false)).unwrap().taker_fee, 20_000);
+
+        // royalties are still paid
+        let fees = calc_fees_with_royalties(args(true), 500, Some(100)).unwrap();
+        assert_eq!(fees.taker_fee, 0);
+        assert_eq!(fees.creators_fee, 50_000);
+        assert_eq!(fees.seller_net, 950_000);
+    }
+
+    #[test]
+    fn test_calc_fees_discount_bps() {
+        let fees = |discount_bps| {
+            calc_fees(CalcFeesArgs {
+                amount: 1_000_000,
+                total_fee_bps: 200,
+                broker_fee_pct: 50,
+                maker_broker_pct: 80,
+                discount_bps,
+                is_fee_exempt: false,
+            })
+            .unwrap()
+            .taker_fee
+        };
+
+        // (discount tier, taker fee)
+        for (discount_bps, taker_fee) in [
+            (None, 20_000),
+            (Some(0), 20_000),
+            (Some(1_000), 18_000),
+            (Some(2_500), 15_000),
+            (Some(4_000), 12_000),
+            (Some(10_000), 0),
+        ] {
+            assert_eq!(fees(discount_bps), taker_fee);
+        }
+
+        // discounts over 100% saturate to a zero fee
+        for discount_bps in [10_001, 50_000, u64::MAX] {
+            assert_eq!(fees(Some(discount_bps)), 0);
+        }
+
+        // the helper constructor applies the standard TNSR discount
+        let discounted =
+            calc_fees(CalcFeesArgs::with_tnsr_discount(1_000_000, 200, 50, 80)).unwrap();
+        assert_eq!(discounted.taker_fee, fees(Some(TNSR_DISCOUNT_BPS)));
+        assert_eq!(discounted.taker_fee, 15_000);
+    }
+
+    #[test]
+    fn test_legacy_fee_model() {
+        // (amount, fee_bps, taker_broker_pct, protocol fee, taker broker fee)
+        for (amount, fee_bps, taker_broker_pct, protocol_fee, taker_broker_fee) in [
+            (1_000_000, 150, 0, 15_000, 0),
+            (1_000_000, 150, 50, 7_500, 7_500),
+            (1_000_000, 150, 100, 0, 15_000),
+            (999_999, 140, 33, 9_380, 4_619),
+            (1, 150, 50, 0, 0),
+        ] {
+            let fees = LegacyFeeModel {
+                fee_bps,
+                taker_broker_pct,
+            }
+            .compute(amount)
+            .unwrap();
+
+            assert_eq!(fees.protocol_fee, protocol_fee);
+            assert_eq!(fees.taker_broker_fee, taker_broker_fee);
+            assert_eq!(fees.maker_broker_fee, 0);
+            assert_eq!(fees.taker_fee, protocol_fee + taker_broker_fee);
+        }
+    }
+
+    #[test]
+    fn test_calc_fees_with_config() {
+        let standard = FeeConfig::default();
+        let discounted = FeeConfig {
+            discount_bps: Some(TNSR_DISCOUNT_BPS),
+            ..FeeConfig::default()
+        };
+        let low_tier = FeeConfig {
+            total_fee_bps: 50,
+            broker_fee_pct: 0,
+            maker_broker_pct: 0,
+            ..FeeConfig::default()
+        };
+
+        // (config, taker fee, protocol fee, maker broker fee, taker broker fee)
+        for (config, taker_fee, protocol_fee, maker_broker_fee, taker_broker_fee) in [
+            (standard, 20_000, 10_000, 8_000, 2_000),
+            (discounted, 15_000, 7_500, 6_000, 1_500),
+            (low_tier, 5_000, 5_000, 0, 0),
+        ] {
+            let fees = calc_fees_with_config(1_000_000, &config).unwrap();
+            assert_eq!(fees.taker_fee, taker_fee);
+            assert_eq!(fees.protocol_fee, protocol_fee);
+            assert_eq!(fees.maker_broker_fee, maker_broker_fee);
+            assert_eq!(fees.taker_broker_fee, taker_broker_fee);
+
+            // same fees through the fee model
+            let fees = config.compute(1_000_000).unwrap();
+            assert_eq!(fees.taker_fee, taker_fee);
+            assert_eq!(fees.protocol_fee, protocol_fee);
+        }
+    }
+
+    #[test]
+    fn test_calc_fees_with_royalties() {
+        for amount in [0, 1, 99, 12_345, 1_000_000, 987_654_321, u64::MAX / 10_000] {
+            for total_fee_bps in [0, 1, 150, 200, 333] {
+                for (seller_fee_basis_points, royalty_pct) in [
+                    (0, None),
+                    (500, None),
+                    (500, Some(100)),
+                    (777, Some(33)),
+                    (1_000, Some(50)),
+                ] {
+                    let fees = calc_fees_with_royalties(
+                        CalcFeesArgs {
+                            amount,
+                            total_fee_bps,
+                            broker_fee_pct: BROKER_FEE_PCT,
+                            maker_broker_pct: MAKER_BROKER_PCT,
+                            discount_bps: (amount % 2 == 0).then_some(TNSR_DISCOUNT_BPS),
+                            is_fee_exempt: false,
+                        },
+                        seller_fee_basis_points,
+                        royalty_pct,
+                    )
+                    .unwrap();
+
+                    assert_eq!(fees.taker_fee + fees.creators_fee + fees.seller_net, amount);
+                    assert_eq!(
+                        fees.protocol_fee + fees.maker_broker_fee + fees.taker_broker_fee,
+                        fees.taker_fee
+                    );
+                    assert_eq!(
+                        fees.creators_fee,
+                        calc_creators_fee(seller_fee_basis_points, amount, royalty_pct).unwrap()
+                    );
+                }
+            }
+        }
+
+        // fees and royalties exceed the amount
+        assert!(calc_fees_with_royalties(
+            CalcFeesArgs {
+                amount: 1_000,
+                total_fee_bps: 5_000,
+                broker_fee_pct: 0,
+                maker_broker_pct: 0,
+                discount_bps: None,
+                is_fee_exempt: false,
+            },
+            6_000,
+            Some(100),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_calc_fees_checked_overflow_step() {
+        let args = |amount, total_fee_bps, broker_fee_pct, maker_broker_pct, discount: bool| {
+            CalcFeesArgs {
+                amount,
+                total_fee_bps,
+                broker_fee_pct,
+                maker_broker_pct,
+                discount_bps: discount.then_some(TNSR_DISCOUNT_BPS),
+                is_fee_exempt: false,
+            }
+        };
+
+        for (args, step) in [
+            (
+                args(1_000_000, u64::MAX, 50, 80, true),
+                FeeCalcStep::Discount,
+            ),
+            (args(u64::MAX, 200, 50, 80, false), FeeCalcStep::TotalFee),
+            (
+                args(1_000_000, 200, u64::MAX, 80, false),
+                FeeCalcStep::BrokerFee,
+            ),
+            (
+                args(1_000_000, 200, 101, 80, false),
+                FeeCalcStep::ProtocolFee,
+            ),
+            (
+                args(1_000_000, 200, 50, u64::MAX, false),
+                FeeCalcStep::MakerBroker,
+            ),
+            (
+                args(1_000_000, 200, 50, 101, false),
+                FeeCalcStep::TakerBroker,
+            ),
+        ] {
+            assert_eq!(calc_fees_checked(args).err(), Some(FeeCalcError { step }));
+        }
+
+        assert!(calc_fees_checked(args(1_000_000, 200, 50, 80, true)).is_ok());
+
+        // the anchor error is preserved
+        assert_eq!(
+            calc_fees(args(u64::MAX, 200, 50, 80, false)).err(),
+            Some(VipersError::IntegerOverflow.into())
+        );
+    }
+
+    #[test]
+    fn test_calc_fees_with_ppm_config() {
+        let ppm = FeeConfig {
+            total_fee_bps: 20_000,
+            bps_denominator: 1_000_000,
+            ..FeeConfig::default()
+        };
+
+        // same fees as the default basis points config
+        for (discount_bps, ppm_discount) in [(None, None), (Some(TNSR_DISCOUNT_BPS), Some(250_000))]
+        {
+            for amount in [0, 1, 12_345, 1_000_000, 987_654_321] {
+                let fees = calc_fees_with_config(
+                    amount,
+                    &FeeConfig {
+                        discount_bps: ppm_discount,
+                        ..ppm
+                    },
+                )
+                .unwrap();
+                let expected = calc_fees(CalcFeesArgs {
+                    amount,
+                    total_fee_bps: TAKER_FEE_BPS,
+                    broker_fee_pct: BROKER_FEE_PCT,
+                    maker_broker_pct: MAKER_BROKER_PCT,
+                    discount_bps,
+                    is_fee_exempt: false,
+                })
+                .unwrap();
+
+                assert_eq!(fees.taker_fee, expected.taker_fee);
+                assert_eq!(fees.protocol_fee, expected.protocol_fee);
+                assert_eq!(fees.maker_broker_fee, expected.maker_broker_fee);
+                assert_eq!(fees.taker_broker_fee, expected.taker_broker_fee);
+            }
+        }
+
+        // finer denomination: 0.0125% with a 25% discount
+        let fees = calc_fees_with_config(
+            10_000_000,
+            &FeeConfig {
+                total_fee_bps: 125,
+                discount_bps: Some(250_000),
+                ..ppm
+            },
+        )
+        .unwrap();
+        assert_eq!(fees.taker_fee, 930);
+
+        // discounts over 100% saturate to a zero fee
+        let fees = calc_fees_with_config(
+            1_000_000,
+            &FeeConfig {
+                discount_bps: Some(2_000_000),
+                ..ppm
+            },
+        )
+        .unwrap();
+        assert_eq!(fees.taker_fee, 0);
+    }
+
+    #[test]
+    fn test_minimum_viable_price() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let rent_per_account = Rent::default().minimum_balance(spl_token::state::Account::LEN);
+
+        for fee_config in [
+            FeeConfig::default(),
+            FeeConfig {
+                discount_bps: Some(TNSR_DISCOUNT_BPS),
+                ..FeeConfig::default()
+            },
+            FeeConfig {
+                total_fee_bps: 0,
+                ..FeeConfig::default()
+            },
+            FeeConfig {
+                total_fee_bps: 333,
+                ..FeeConfig::default()
+            },
+            FeeConfig {
+                total_fee_bps: 9_999,
+                ..FeeConfig::default()
+            },
+            FeeConfig {
+                total_fee_bps: 12_345,
+                bps_denominator: 1_000_000,
+                ..FeeConfig::default()
+            },
+        ] {
+            assert_eq!(minimum_viable_price(&fee_config, 0).unwrap(), 0);
+
+            for num_accounts in 1..=4 {
+                let rent = rent_per_account * num_accounts as u64;
+                let price = minimum_viable_price(&fee_config, num_accounts).unwrap();
+                let net =
+                    |price| price - calc_fees_with_config(price, &fee_config).unwrap().taker_fee;
+
+                // covers the rent, and it is the smallest price that does
+                assert!(net(price) >= rent);
+                assert!(net(price - 1) < rent);
+            }
+        }
+
+        // no fee: the price is the rent
+        assert_eq!(
+            minimum_viable_price(
+                &FeeConfig {
+                    total_fee_bps: 0,
+                    ..FeeConfig::default()
+                },
+                2
+            )
+            .unwrap(),
+            2 * rent_per_account
+        );
+
+        // a 100% fee cannot cover the rent
+        assert!(minimum_viable_price(
+            &FeeConfig {
+                total_fee_bps: HUNDRED_PCT_BPS,
+                ..FeeConfig::default()
+            },
+            1
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_seller_net_proceeds() {
+        let config = FeeConfig::default();
+
+        // 2% taker fee and 5% royalties
+        assert_eq!(
+            seller_net_proceeds(1_000_000, &config, 500, Some(100)).unwrap(),
+            930_000
+        );
+        // no optional royalties
+        assert_eq!(
+            seller_net_proceeds(1_000_000, &config, 500, None).unwrap(),
+            980_000
+        );
+        // half of the royalties
+        assert_eq!(
+            seller_net_proceeds(1_000_000, &config, 500, Some(50)).unwrap(),
+            955_000
+        );
+        // fees and royalties equal to the sale price
+        assert_eq!(
+            seller_net_proceeds(1_000_000, &config, 9_800, Some(100)).unwrap(),
+            0
+        );
+        // fees and royalties exceed the sale price
+        assert_eq!(
+            seller_net_proceeds(1_000_000, &config, 9_900, Some(100)),
+            Err(TensorError::InsufficientBalance.into())
+        );
+    }
+
+    #[test]
+    fn test_redistribute_absent_broker_fees() {
+        let (maker_broker, taker_broker) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let fees = test_fees();
+        assert_eq!(fees.taker_fee, 20_000);
+        assert_eq!(fees.protocol_fee, 10_000);
+        assert_eq!(fees.maker_broker_fee, 8_000);
+        assert_eq!(fees.taker_broker_fee, 2_000);
+
+        // Missing maker broker.
+        let fees = redistribute_absent_broker_fees(test_fees(), None, Some(&taker_broker)).unwrap();
+        assert_eq!(fees.taker_fee, 20_000);
+        assert_eq!(fees.protocol_fee, 18_000);
+        assert_eq!(fees.maker_broker_fee, 0);
+        assert_eq!(fees.taker_broker_fee, 2_000);
+
+        // Missing taker broker.
+        let fees = redistribute_absent_broker_fees(test_fees(), Some(&maker_broker), None).unwrap();
+        assert_eq!(fees.taker_fee, 20_000);
+        assert_eq!(fees.protocol_fee, 12_000);
+        assert_eq!(fees.maker_broker_fee, 8_000);
+        assert_eq!(fees.taker_broker_fee, 0);
+
+        // Both brokers present.
+        let fees =
+            redistribute_absent_broker_fees(test_fees(), Some(&maker_broker), Some(&taker_broker))
+                .unwrap();
+        assert_eq!(fees.taker_fee, 20_000);
+        assert_eq!(fees.protocol_fee, 10_000);
+        assert_eq!(fees.maker_broker_fee, 8_000);
+        assert_eq!(fees.taker_broker_fee, 2_000);
+
+        // Both brokers missing.
+        let fees = redistribute_absent_broker_fees(test_fees(), None, None).unwrap();
+        assert_eq!(fees.protocol_fee, fees.taker_fee);
+        assert_eq!(fees.maker_broker_fee + fees.taker_broker_fee, 0);
+    }
+
+    #[test]
+    fn test_assert_ata_program_matches() {
+        let ata_program = anchor_spl::associated_token::ID;
+
+        for token_program in [spl_token::ID, spl_token_2022::ID] {
+            assert!(assert_ata_program_matches(&ata_program, &token_program).is_ok());
+        }
+
+        // (ata program, token program)
+        for (ata_program, token_program) in [
+            (ata_program, system_program::ID),
+            (ata_program, ata_program),
+            (spl_token::ID, spl_token::ID),
+            (spl_token_2022::ID, spl_token_2022::ID),
+            (Pubkey::new_unique(), spl_token::ID),
+        ] {
+            assert_eq!(
+                assert_ata_program_matches(&ata_program, &token_program),
+                Err(ErrorCode::InvalidProgramId.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_native_mint() {
+        for native in [spl_token::native_mint::ID, spl_token_2022::native_mint::ID] {
+            assert!(is_native_mint(&native));
+            assert!(assert_native_mint(&native).is_ok());
+            assert_eq!(
+                assert_not_native_mint(&native),
+                Err(TensorError::InvalidMint.into())
+            );
+        }
+
+        let mint = Pubkey::new_unique();
+        assert!(!is_native_mint(&mint));
+        assert!(assert_not_native_mint(&mint).is_ok());
+        assert_eq!(
+            assert_native_mint(&mint),
+            Err(TensorError::InvalidMint.into())
+        );
+    }
+
+    #[test]
+    fn test_assert_not_self_transfer() {
+        let (source, destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(assert_not_self_transfer(&source, &destination).is_ok());
+        assert_eq!(
+            assert_not_self_transfer(&source, &source),
+            Err(TensorError::SelfTransfer.into())
+        );
+    }
+
+    #[test]
+    fn test_effective_royalty_bps() {
+        // Full royalties.
+        assert_eq!(effective_royalty_bps(500, Some(100)).unwrap(), 500);
+        assert_eq!(effective_royalty_bps(10_000, Some(100)).unwrap(), 10_000);
+        // No optional royalties.
+        assert_eq!(effective_royalty_bps(500, None).unwrap(), 0);
+        assert_eq!(effective_royalty_bps(500, Some(0)).unwrap(), 0);
+        // Partial royalties.
+        assert_eq!(effective_royalty_bps(500, Some(50)).unwrap(), 250);
+        assert_eq!(effective_royalty_bps(333, Some(50)).unwrap(), 166);
+        // Invalid pct.
+        assert!(effective_royalty_bps(500, Some(101)).is_err());
+
+        // Consistent with the creators fee charged.
+        assert_eq!(
+            calc_creators_fee(333, HUNDRED_PCT_BPS, Some(50)).unwrap(),
+            effective_royalty_bps(333, Some(50)).unwrap() as u64
+        );
+    }
+
+    #[test]
+    fn test_cpi_invoke() {
+        let program = program_account(escrow::ID);
+
+        assert!(cpi_invoke(program, &escrow::ID, vec![], vec![], &[], None).is_ok());
+        assert!(cpi_invoke(program, &escrow::ID, vec![], vec![], &[], Some(&[])).is_ok());
+        assert!(cpi_invoke(program, &fees::ID, vec![], vec![], &[], None).is_err());
+    }
+
+    #[test]
+    fn test_signer_seeds() {
+        let owner = Pubkey::new_unique();
+        let (pda, bump) = Pubkey::find_program_address(&[b"escrow", owner.as_ref()], &escrow::ID);
+
+        let mut seeds = SignerSeeds::new(&[b"escrow", owner.as_ref()], bump);
+        seeds.with_signer_seeds(|signer_seeds| {
+            assert_eq!(signer_seeds.len(), 1);
+            assert_eq!(
+                signer_seeds[0],
+                &[b"escrow".as_ref(), owner.as_ref(), &[bump]]
+            );
+            assert_eq!(
+                Pubkey::create_program_address(signer_seeds[0], &escrow::ID).unwrap(),
+                pda
+            );
+        });
+
+        let (other, other_bump) = Pubkey::find_program_address(&[b"fees"], &fees::ID);
+        seeds.add_signer(&[b"fees"], other_bump);
+        seeds.with_signer_seeds(|signer_seeds| {
+            assert_eq!(signer_seeds.len(), 2);
+            assert_eq!(
+                Pubkey::create_program_address(signer_seeds[1], &fees::ID).unwrap(),
+                other
+            );
+        });
+
+        // usable with the helpers taking borrowed seeds
+        let program = program_account(escrow::ID);
+        assert!(seeds
+            .with_signer_seeds(|signer_seeds| {
+                cpi_invoke(
+                    program,
+                    &escrow::ID,
+                    vec![],
+                    vec![],
+                    &[],
+                    Some(signer_seeds),
+                )
+            })
+            .is_ok());
+    }
+
+    #[test]
+    fn test_estimate_creators_fee_cu() {
+        for mode in [FeeMode::Sol, FeeMode::Spl] {
+            let mut previous = estimate_creators_fee_cu(0, mode);
+            assert_eq!(previous, CREATORS_FEE_BASE_CU);
+
+            for num_creators in 1..=5 {
+                let estimate = estimate_creators_fee_cu(num_creators, mode);
+                assert!(estimate > previous);
+                previous = estimate;
+            }
+        }
+
+        assert!(
+            estimate_creators_fee_cu(1, FeeMode::Spl) > estimate_creators_fee_cu(1, FeeMode::Sol)
+        );
+        assert_eq!(estimate_creators_fee_cu(usize::MAX, FeeMode::Spl), u32::MAX);
+    }
+
+    fn lamports_account(owner: Pubkey, lamports: u64) -> AccountInfo<'static> {
+        let account = account_info(Pubkey::new_unique(), owner, vec![]);
+        **account.lamports.borrow_mut() = lamports;
+        account
+    }
+
+    #[test]
+    fn test_close_account_metered() {
+        let balance = Rent::default().minimum_balance(0) + 10;
+        let mut pda = lamports_account(escrow::ID, balance);
+        let mut destination = lamports_account(system_program::ID, 1_000);
+
+        assert_eq!(
+            close_account_metered(&mut pda, &mut destination).unwrap(),
+            balance
+        );
+        assert_eq!(destination.lamports(), 1_000 + balance);
+        assert_eq!(pda.lamports(), 0);
+        assert_eq!(pda.owner, &system_program::ID);
+    }
+
+    #[test]
+    fn test_credit_and_debit_lamports() {
+        let account = lamports_account(system_program::ID, u64::MAX - 10);
+
+        credit_lamports(&account, 10).unwrap();
+        assert_eq!(account.lamports(), u64::MAX);
+
+        assert_eq!(
+            credit_lamports(&account, 1),
+            Err(VipersError::IntegerOverflow.into())
+        );
+        assert_eq!(account.lamports(), u64::MAX);
+
+        debit_lamports(&account, u64::MAX).unwrap();
+        assert_eq!(account.lamports(), 0);
+
+        assert_eq!(
+            debit_lamports(&account, 1),
+            Err(VipersError::IntegerOverflow.into())
+        );
+        assert_eq!(account.lamports(), 0);
+
+        // zero amounts are no-ops on both ends of the range
+        credit_lamports(&account, 0).unwrap();
+        debit_lamports(&account, 0).unwrap();
+        assert_eq!(account.lamports(), 0);
+    }
+
+    #[test]
+    fn test_transfer_creators_fee_from_slice() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let pda = lamports_account(escrow::ID, 1_000_000_000);
+        let accounts = [
+            lamports_account(system_program::ID, 1_000_000),
+            lamports_account(system_program::ID, 1_000_000),
+            lamports_account(system_program::ID, 1_000_000),
+        ];
+        let creators = vec![
+            TCreator {
+                address: *accounts[0].key,
+                verified: true,
+                share: 60,
+            },
+            TCreator {
+                address: *accounts[1].key,
+                verified: false,
+                share: 40,
+            },
+        ];
+        let from = FromAcc::Pda(&pda);
+        let mode = CreatorFeeMode::Sol { from: &from };
+
+        let (sent, remaining) =
+            transfer_creators_fee_from_slice(&creators, &accounts, 1_001, &mode).unwrap();
+        assert_eq!(sent, 600 + 400);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].key, accounts[2].key);
+        assert_eq!(accounts[0].lamports(), 1_000_600);
+        assert_eq!(accounts[1].lamports(), 1_000_400);
+
+        // creators that cannot be paid still consume their account
+        let poor = lamports_account(system_program::ID, 0);
+        let creators = vec![TCreator {
+            address: *poor.key,
+            verified: true,
+            share: 100,
+        }];
+        let (sent, remaining) =
+            transfer_creators_fee_from_slice(&creators, std::slice::from_ref(&poor), 1, &mode)
+                .unwrap();
+        assert_eq!(sent, 0);
+        assert!(remaining.is_empty());
+
+        // accounts out of order
+        let creators = vec![TCreator {
+            address: *accounts[1].key,
+            verified: true,
+            share: 100,
+        }];
+        assert!(transfer_creators_fee_from_slice(&creators, &accounts, 100, &mode).is_err());
+
+        // missing creator accounts
+        assert!(transfer_creators_fee_from_slice(&creators, &[], 100, &mode).is_err());
+    }
+
+    fn program_account(key: Pubkey) -> &'static AccountInfo<'static> {
+        let mut account = account_info(key, Pubkey::default(), vec![]);
+        account.executable = true;
+        Box::leak(Box::new(account))
+    }
+
+    fn zero_share_creators(accounts: &[&AccountInfo]) -> Vec<TCreator> {
+        [60, 0, 40]
+            .into_iter()
+            .zip(accounts)
+            .map(|(share, account)| TCreator {
+                address: *account.key,
+                verified: true,
+                share,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_transfer_creators_fee_zero_share_sol() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let pda = lamports_account(escrow::ID, 1_000_000_000);
+        let accounts = [
+            lamports_account(system_program::ID, 1_000_000),
+            lamports_account(system_program::ID, 1_000_000),
+            lamports_account(system_program::ID, 1_000_000),
+        ];
+        let creators = zero_share_creators(&accounts.iter().collect::<Vec<_>>());
+        let from = FromAcc::Pda(&pda);
+        let mode = CreatorFeeMode::Sol { from: &from };
+
+        // same layout in both variants: one account per creator
+        let mut iter = accounts.iter();
+        let sent = transfer_creators_fee(&creators, &mut iter, 1_000, &mode).unwrap();
+        assert_eq!(sent, 1_000);
+        assert!(iter.as_slice().is_empty());
+
+        let mut iter = accounts.iter();
+        let sent = transfer_creators_fee_compact(&creators, &mut iter, 1_000, &mode, None).unwrap();
+        assert_eq!(sent, 1_000);
+        assert!(iter.as_slice().is_empty());
+
+        assert_eq!(accounts[0].lamports(), 1_001_200);
+        assert_eq!(accounts[1].lamports(), 1_000_000);
+        assert_eq!(accounts[2].lamports(), 1_000_800);
+    }
+
+    #[test]
+    fn test_transfer_creators_fee_zero_share_spl() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let associated_token_program =
+            Program::try_from(program_account(anchor_spl::associated_token::ID)).unwrap();
+        let token_program = Interface::try_from(program_account(spl_token::ID)).unwrap();
+        let system_program = Program::try_from(program_account(system_program::ID)).unwrap();
+        let currency = lamports_account(spl_token::ID, 0);
+        let from = lamports_account(system_program::ID, 0);
+        let from_token_acc = lamports_account(spl_token::ID, 0);
+        let rent_payer = lamports_account(system_program::ID, 1_000_000_000);
+        let mode = CreatorFeeMode::Spl {
+            associated_token_program: &associated_token_program,
+            token_program: &token_program,
+            system_program: &system_program,
+            currency: &currency,
+            from: &from,
+            from_token_acc: &from_token_acc,
+            rent_payer: &rent_payer,
+        };
+
+        let creators_accounts = [
+            lamports_account(system_program::ID, 0),
+            lamports_account(system_program::ID, 0),
+            lamports_account(system_program::ID, 0),
+        ];
+        let atas = [
+            lamports_account(system_program::ID, 0),
+            lamports_account(system_program::ID, 0),
+            lamports_account(system_program::ID, 0),
+        ];
+        let creators = zero_share_creators(&creators_accounts.iter().collect::<Vec<_>>());
+        let tail = lamports_account(system_program::ID, 0);
+
+        // full layout: the ATA of the 0% creator is consumed but not used
+        let accounts = [
+            creators_accounts[0].clone(),
+            atas[0].clone(),
+            creators_accounts[1].clone(),
+            atas[1].clone(),
+            creators_accounts[2].clone(),
+            atas[2].clone(),
+            tail.clone(),
+        ];
+        let mut iter = accounts.iter();
+        let sent = transfer_creators_fee(&creators, &mut iter, 1_000, &mode).unwrap();
+        assert_eq!(sent, 1_000);
+        assert_eq!(iter.as_slice().len(), 1);
+        assert_eq!(iter.as_slice()[0].key, tail.key);
+
+        // ATA creation and transfer for each paid creator only
+        let invoked = crate::test_utils::take_invoked_instructions();
+        assert_eq!(invoked.len(), 4);
+        assert!(invoked
+            .iter()
+            .all(|ix| !ix.accounts.iter().any(|meta| meta.pubkey == *atas[1].key)));
+
+        // compact layout: no ATA for the 0% creator
+        let accounts = [
+            creators_accounts[0].clone(),
+            atas[0].clone(),
+            creators_accounts[1].clone(),
+            creators_accounts[2].clone(),
+            atas[2].clone(),
+            tail.clone(),
+        ];
+        let mut iter = accounts.iter();
+        let sent = transfer_creators_fee_compact(&creators, &mut iter, 1_000, &mode, None).unwrap();
+        assert_eq!(sent, 1_000);
+        assert_eq!(iter.as_slice().len(), 1);
+        assert_eq!(iter.as_slice()[0].key, tail.key);
+        assert_eq!(crate::test_utils::take_invoked_instructions().len(), 4);
+
+        // the full layout is rejected by the compact variant
+        let accounts = [
+            creators_accounts[0].clone(),
+            atas[0].clone(),
+            creators_accounts[1].clone(),
+            atas[1].clone(),
+            creators_accounts[2].clone(),
+            atas[2].clone(),
+        ];
+        assert!(
+            transfer_creators_fee_compact(&creators, &mut accounts.iter(), 1_000, &mode, None)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn test_drain_token_account_transfer_fee() {
+        use anchor_spl::token_interface::spl_token_2022::{
+            extension::{
+                transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig},
+                BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+                StateWithExtensionsMut,
+            },
+            instruction::TokenInstruction,
+            processor::Processor,
+        };
+
+        use crate::token_2022::test_utils::{
+            mint_data_with_raw_extensions, token_account_data_with_raw_extensions,
+        };
+
+        crate::test_utils::setup_syscall_stubs();
+
+        // 1% transfer fee, capped at 100 tokens
+        let transfer_fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: 100.into(),
+            transfer_fee_basis_points: 100.into(),
+        };
+        let fee_config = TransferFeeConfig {
+            older_transfer_fee: transfer_fee,
+            newer_transfer_fee: transfer_fee,
+            ..Default::default()
+        };
+        let mint = account_info(
+            Pubkey::new_unique(),
+            spl_token_2022::ID,
+            mint_data_with_raw_extensions(&[(
+                ExtensionType::TransferFeeConfig as u16,
+                bytemuck::bytes_of(&fee_config),
+            )]),
+        );
+        let mut authority = lamports_account(system_program::ID, 0);
+        authority.is_signer = true;
+        let token_account = |mint: &Pubkey, amount| {
+            let mut data = token_account_data_with_raw_extensions(
+                mint,
+                authority.key,
+                &[(
+                    ExtensionType::TransferFeeAmount as u16,
+                    bytemuck::bytes_of(&TransferFeeAmount::default()),
+                )],
+            );
+            let mut account =
+                StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack(&mut data)
+                    .unwrap();
+            account.base.amount = amount;
+            account.pack_base();
+
+            account_info(Pubkey::new_unique(), spl_token_2022::ID, data)
+        };
+
+        let from_ata = token_account(mint.key, 12_345);
+        let to_ata = token_account(mint.key, 0);
+        let token_program = program_account(spl_token_2022::ID);
+
+        let moved = drain_token_account(&from_ata, &to_ata, &mint, &authority, token_program, None)
+            .unwrap();
+        assert_eq!(moved, 12_345);
+
+        // the full balance is sent
+        let invoked = crate::test_utils::take_invoked_instructions();
+        assert_eq!(invoked.len(), 1);
+        assert_eq!(invoked[0].program_id, spl_token_2022::ID);
+        assert_eq!(
+            TokenInstruction::unpack(&invoked[0].data).unwrap(),
+            TokenInstruction::TransferChecked {
+                amount: 12_345,
+                decimals: 0
+            }
+        );
+
+        // while the destination receives the amount minus the fee: 1% of 12,345 rounds up to
+        // 124, which is capped at 100
+        Processor::process(
+            &spl_token_2022::ID,
+            &[
+                from_ata.clone(),
+                mint.clone(),
+                to_ata.clone(),
+                authority.clone(),
+            ],
+            &invoked[0].data,
+        )
+        .unwrap();
+
+        let data = from_ata.data.borrow();
+        let from = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data).unwrap();
+        assert_eq!(from.base.amount, 0);
+
+        let data = to_ata.data.borrow();
+        let to = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data).unwrap();
+        assert_eq!(to.base.amount, 12_245);
+        assert_eq!(
+            u64::from(
+                to.get_extension::<TransferFeeAmount>()
+                    .unwrap()
+                    .withheld_amount
+            ),
+            100
+        );
+        drop(data);
+
+        // empty accounts are a no-op
+        assert_eq!(
+            drain_token_account(&from_ata, &to_ata, &mint, &authority, token_program, None)
+                .unwrap(),
+            0
+        );
+        assert!(crate::test_utils::take_invoked_instructions().is_empty());
+
+        // only token programs are invoked
+        let other_program = program_account(Pubkey::new_unique());
+        assert!(
+            drain_token_account(&to_ata, &from_ata, &mint, &authority, other_program, None)
+                .is_err()
+        );
+
+        // the token account must be of the given mint
+        let other_mint = token_account(&Pubkey::new_unique(), 1);
+        assert_eq!(
+            drain_token_account(&other_mint, &to_ata, &mint, &authority, token_program, None),
+            Err(TensorError::InvalidMint.into())
+        );
+        assert!(crate::test_utils::take_invoked_instructions().is_empty());
+    }
+
+    #[test]
+    fn test_compute_creator_distribution() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let pda = lamports_account(escrow::ID, u64::MAX / 2);
+        let from = FromAcc::Pda(&pda);
+        let mode = CreatorFeeMode::Sol { from: &from };
+
+        for shares in [
+            vec![100],
+            vec![50, 50],
+            vec![33, 33, 34],
+            vec![1, 2, 3, 4, 90],
+            vec![0, 100],
+        ] {
+            let accounts = shares
+                .iter()
+                .map(|_| lamports_account(system_program::ID, 1_000_000))
+                .collect::<Vec<_>>();
+            let creators = shares
+                .iter()
+                .zip(accounts.iter())
+                .map(|(share, account)| TCreator {
+                    address: *account.key,
+                    verified: true,
+                    share: *share,
+                })
+                .collect::<Vec<_>>();
+
+            for creator_fee in [0, 1, 99, 1_001, 123_456_789] {
+                let before = accounts.iter().map(|a| a.lamports()).collect::<Vec<_>>();
+                let distribution = compute_creator_distribution(&creators, creator_fee);
+
+                let sent =
+                    transfer_creators_fee(&creators, &mut accounts.iter(), creator_fee, &mode)
+                        .unwrap();
+
+                assert_eq!(
+                    distribution.iter().map(|(_, amount)| amount).sum::<u64>(),
+                    sent
+                );
+                for (i, (address, amount)) in distribution.iter().enumerate() {
+                    assert_eq!(address, accounts[i].key);
+                    assert_eq!(accounts[i].lamports() - before[i], *amount);
+                }
+            }
+        }
+
+        assert_eq!(
+            compute_creator_distribution(&[], 1_000),
+            Vec::<(Pubkey, u64)>::new()
+        );
+    }
+
+    #[test]
+    fn test_transfer_creators_fee_with_fallback() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let pda = lamports_account(escrow::ID, 1_000_000_000);
+        let paid = lamports_account(system_program::ID, 1_000_000);
+        let poor = lamports_account(system_program::ID, 0);
+        let treasury = lamports_account(system_program::ID, 1_000_000);
+        let creators = vec![
+            TCreator {
+                address: *paid.key,
+                verified: true,
+                share: 70,
+            },
+            TCreator {
+                address: *poor.key,
+                verified: false,
+                share: 30,
+            },
+        ];
+        let from = FromAcc::Pda(&pda);
+        let mode = CreatorFeeMode::Sol { from: &from };
+        let accounts = [paid.clone(), poor.clone()];
+
+        // the poor creator is skipped and their share goes to the fallback
+        let sent = transfer_creators_fee_with_fallback(
+            &creators,
+            &mut accounts.iter(),
+            1_001,
+            &mode,
+            Some(&treasury),
+        )
+        .unwrap();
+        assert_eq!(sent, 700 + 300);
+        assert_eq!(paid.lamports(), 1_000_700);
+        assert_eq!(poor.lamports(), 0);
+        assert_eq!(treasury.lamports(), 1_000_300);
+        // dust stays in the source
+        assert_eq!(pda.lamports(), 1_000_000_000 - 1_000);
+
+        // without a fallback, the skipped share stays in the source
+        let sent = transfer_creators_fee_with_fallback(
+            &creators,
+            &mut accounts.iter(),
+            1_000,
+            &mode,
+            None,
+        )
+        .unwrap();
+        assert_eq!(sent, 700);
+        assert_eq!(treasury.lamports(), 1_000_300);
+        assert_eq!(pda.lamports(), 1_000_000_000 - 1_700);
+    }
+
+    #[test]
+    fn test_transfer_lamports_or_err() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let pda = lamports_account(escrow::ID, 1_000_000_000);
+        let rent = Rent::default().minimum_balance(0);
+
+        // not enough to be rent exempt: skipped by the checked variant, error otherwise
+        let poor = lamports_account(system_program::ID, 0);
+        transfer_lamports_checked(&pda, &poor, rent - 1).unwrap();
+        assert_eq!(poor.lamports(), 0);
+        assert_eq!(
+            transfer_lamports_or_err(&pda, &poor, rent - 1).unwrap_err(),
+            TensorError::WouldNotBeRentExempt.into()
+        );
+        assert_eq!(poor.lamports(), 0);
+        assert_eq!(pda.lamports(), 1_000_000_000);
+
+        // enough to be rent exempt: both transfer
+        transfer_lamports_checked(&pda, &poor, rent).unwrap();
+        assert_eq!(poor.lamports(), rent);
+        transfer_lamports_or_err(&pda, &poor, 1).unwrap();
+        assert_eq!(poor.lamports(), rent + 1);
+        assert_eq!(pda.lamports(), 1_000_000_000 - rent - 1);
+    }
+
+    #[test]
+    fn test_assert_fee_account_with_shard() {
+        let fee_vault = |shard: u8| {
+            let key = Pubkey::find_program_address(&[b"fee_vault", &[shard]], &fees::ID).0;
+            account_info(key, system_program::ID, vec![])
+        };
+
+        // correct shard
+        assert!(assert_fee_account_with_shard(&fee_vault(7), 7).is_ok());
+        // wrong shard
+        assert_eq!(
+            assert_fee_account_with_shard(&fee_vault(7), 8).unwrap_err(),
+            TensorError::InvalidFeeAccount.into()
+        );
+
+        // the singleton is accepted for any shard
+        let mut singleton = fee_vault(0);
+        singleton.key = &marketplace::TCOMP_SINGLETON;
+        for shard in [0, 7, u8::MAX] {
+            assert!(assert_fee_account_with_shard(&singleton, shard).is_ok());
+        }
+
+        // the state variant uses the last byte of the state key
+        let mut state = lamports_account(system_program::ID, 0);
+        let mut state_key = Pubkey::new_unique().to_bytes();
+        state_key[31] = 42;
+        state.key = Box::leak(Box::new(Pubkey::new_from_array(state_key)));
+        assert!(assert_fee_account(&fee_vault(42), &state).is_ok());
+        assert!(assert_fee_account(&fee_vault(41), &state).is_err());
+        assert!(assert_fee_account(&singleton, &state).is_ok());
+    }
+
+    #[test]
+    fn test_assert_royalties_paid() {
+        // full payment
+        assert!(assert_royalties_paid(1_000, 1_000, 0).is_ok());
+        // rounding dust within tolerance
+        assert!(assert_royalties_paid(998, 1_000, 2).is_ok());
+        // overpayment is not a shortfall
+        assert!(assert_royalties_paid(1_001, 1_000, 0).is_ok());
+
+        // shortfall beyond tolerance
+        assert_eq!(
+            assert_royalties_paid(997, 1_000, 2).unwrap_err(),
+            TensorError::RoyaltiesNotPaid.into()
+        );
+        assert!(assert_royalties_paid(0, 1_000, 0).is_err());
+
+        // skipped creator
+        crate::test_utils::setup_syscall_stubs();
+        let pda = lamports_account(escrow::ID, 1_000_000_000);
+        let paid = lamports_account(system_program::ID, 1_000_000);
+        let poor = lamports_account(system_program::ID, 0);
+        let creators = vec![
+            TCreator {
+                address: *paid.key,
+                verified: true,
+                share: 50,
+            },
+            TCreator {
+                address: *poor.key,
+                verified: true,
+                share: 50,
+            },
+        ];
+        let from = FromAcc::Pda(&pda);
+        let mode = CreatorFeeMode::Sol { from: &from };
+        let accounts = [paid, poor];
+
+        let distributed =
+            transfer_creators_fee(&creators, &mut accounts.iter(), 1_000, &mode).unwrap();
+        assert_eq!(distributed, 500);
+        assert!(assert_royalties_paid(distributed, 1_000, 1).is_err());
+    }
+
+    #[test]
+    fn test_split_remaining_accounts() {
+        let accounts = (0..6)
+            .map(|_| lamports_account(system_program::ID, 0))
+            .collect::<Vec<_>>();
+
+        let groups = split_remaining_accounts(&accounts, &[2, 0, 3, 1]).unwrap();
+        assert_eq!(
+            groups.iter().map(|group| group.len()).collect::<Vec<_>>(),
+            vec![2, 0, 3, 1]
+        );
+        assert_eq!(groups[0][0].key, accounts[0].key);
+        assert_eq!(groups[2][0].key, accounts[2].key);
+        assert_eq!(groups[3][0].key, accounts[5].key);
+
+        // no groups and no accounts
+        assert!(split_remaining_accounts(&[], &[]).unwrap().is_empty());
+
+        // too few and too many accounts
+        for group_sizes in [&[2, 5][..], &[2, 3], &[], &[usize::MAX, 1]] {
+            assert!(split_remaining_accounts(&accounts, group_sizes).is_err());
+        }
+        assert_eq!(
+            split_remaining_accounts(&accounts, &[7]).unwrap_err(),
+            TensorError::RemainingAccountsMismatch.into()
+        );
+    }
+
+    #[test]
+    fn test_lamports_transfer_path() {
+        let mut system = account_info(Pubkey::new_unique(), system_program::ID, vec![]);
+        system.is_signer = true;
+        **system.lamports.borrow_mut() = 1_000_000;
+        let pda = account_info(Pubkey::new_unique(), escrow::ID, vec![0u8; 8]);
+        **pda.lamports.borrow_mut() = 1_000_000;
+
+        // (from, to, expected path)
+        let matrix = [
+            (&system, &system, LamportsTransferPath::SystemTransfer),
+            (&system, &pda, LamportsTransferPath::SystemTransfer),
+            (&pda, &system, LamportsTransferPath::Direct),
+            (&pda, &pda, LamportsTransferPath::Direct),
+        ];
+
+        for (from, to, expected) in matrix {
+            assert_eq!(lamports_transfer_path(from), expected);
+
+            // the system transfer CPI is a no-op off-chain
+            if expected == LamportsTransferPath::SystemTransfer {
+                assert!(transfer_lamports(from, to, 1).is_ok());
+            }
+        }
+    }
+
     #[test]
     #[should_panic]
     fn pubkey_constant_base58_too_short() {
@@ -560,4 +2747,43 @@ mod tests {
     fn pubkey_constant_base58_empty() {
         let _p = pubkey("");
     }
+
+    #[test]
+    fn pubkey_or_none_constant() {
+        const NONE: Option<Pubkey> = pubkey_or_none("");
+        const SOME: Option<Pubkey> = pubkey_or_none("11111111111111111111111111111111");
+
+        assert_eq!(NONE, None);
+        assert_eq!(SOME, Some(Pubkey::default()));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid character (0, O, I or l)")]
+    fn pubkey_or_none_constant_invalid() {
+        let _p = pubkey_or_none("4zdNGgAtFsW1cQgHqkiWyRsxaAgxrSRRynnuunxzjxuO");
+    }
+
+    #[test]
+    #[should_panic(expected = "whitespace")]
+    fn pubkey_constant_base58_whitespace() {
+        let _p = pubkey(" 4zdNGgAtFsW1cQgHqkiWyRsxaAgxrSRRynnuunxzjxue");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid character (0, O, I or l)")]
+    fn pubkey_constant_base58_invalid_char() {
+        let _p = pubkey("4zdNGgAtFsW1cQgHqkiWyRsxaAgxrSRRynnuunxzjxu0");
+    }
+
+    #[test]
+    #[should_panic(expected = "non-alphanumeric character")]
+    fn pubkey_constant_base58_non_alphanumeric_char() {
+        let _p = pubkey("4zdNGgAtFsW1cQgHqkiWyRsxaAgxrSRRynnuunxzjx-e");
+    }
+
+    #[test]
+    #[should_panic(expected = "does not decode to 32 bytes")]
+    fn pubkey_constant_base58_wrong_length() {
+        let _p = pubkey("4zdNGgAtFsW1cQgHqkiWyRsxaAgxrSRRynnuunxzjxuee");
+    }
 }
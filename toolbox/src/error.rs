@@ -43,4 +43,86 @@ pub enum TensorError {
 
     #[msg("invalid owner")]
     InvalidOwner = 9014,
+
+    #[msg("invalid authorization data")]
+    InvalidAuthorizationData = 9015,
+
+    #[msg("source and destination owners must be different")]
+    SelfTransfer = 9016,
+
+    #[msg("invalid collection")]
+    InvalidCollection = 9017,
+
+    #[msg("duplicate creator address")]
+    DuplicateCreator = 9018,
+
+    #[msg("transfer failed")]
+    TransferFailedAtIndex = 9019,
+
+    #[msg("account would not be rent exempt")]
+    WouldNotBeRentExempt = 9020,
+
+    #[msg("invalid destination: must not be the default pubkey")]
+    InvalidDestination = 9021,
+
+    #[msg("remaining accounts count mismatch")]
+    RemainingAccountsMismatch = 9022,
+
+    #[msg("royalties not paid in full")]
+    RoyaltiesNotPaid = 9023,
+
+    #[msg("permanent delegate not allowed")]
+    PermanentDelegateNotAllowed = 9024,
+}
+
+/// Allows returning a [TensorError] from non-Anchor handlers; the custom error code is the
+/// same as the Anchor error code.
+impl From<TensorError> for ProgramError {
+    fn from(error: TensorError) -> Self {
+        Error::from(error).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tensor_vipers::VipersError;
+
+    use super::*;
+
+    fn error_code_number(error: Error) -> u32 {
+        match error {
+            Error::AnchorError(error) => error.error_code_number,
+            Error::ProgramError(_) => panic!("expected an anchor error"),
+        }
+    }
+
+    #[test]
+    fn test_error_conversions() {
+        // Both error types convert into the anchor error, preserving their codes: tensor errors
+        // are offset by the anchor error code offset, vipers errors start at 1100.
+        assert_eq!(
+            error_code_number(TensorError::BadRoyaltiesPct.into()),
+            ERROR_CODE_OFFSET + 9001
+        );
+        assert_eq!(
+            error_code_number(TensorError::CreatorMismatch.into()),
+            ERROR_CODE_OFFSET + 9003
+        );
+        assert_eq!(error_code_number(VipersError::KeyMismatch.into()), 1100);
+
+        // Program errors carry the same custom codes as the anchor errors.
+        assert_eq!(
+            ProgramError::from(TensorError::BadRoyaltiesPct),
+            ProgramError::Custom(ERROR_CODE_OFFSET + 9001)
+        );
+        assert_eq!(
+            ProgramError::from(VipersError::KeyMismatch),
+            ProgramError::Custom(1100)
+        );
+
+        // Codes do not overlap: the last vipers error must stay below the first tensor error.
+        let vipers_max = error_code_number(VipersError::DiscriminatorMismatch.into());
+        let tensor_min = error_code_number(TensorError::BadRoyaltiesPct.into());
+        assert!(vipers_max < tensor_min);
+    }
 }
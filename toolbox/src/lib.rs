@@ -8,6 +8,8 @@ pub mod hash;
 pub mod metaplex_core;
 pub mod nullable;
 pub mod operation;
+#[cfg(test)]
+pub(crate) mod test_utils;
 #[cfg(feature = "token-2022")]
 pub mod token_2022;
 pub mod token_metadata;
@@ -6,12 +6,13 @@ use mpl_core::{
     accounts::{BaseAssetV1, BaseCollectionV1},
     fetch_plugin,
     types::{
-        Creator, Key, PluginType, Royalties, UpdateAuthority, VerifiedCreators,
+        Creator, Key, PluginType, Royalties, RuleSet, UpdateAuthority, VerifiedCreators,
         VerifiedCreatorsSignature,
     },
 };
+use tensor_vipers::{assert::assert_discriminator_u8, prelude::*};
 
-use crate::TensorError;
+use crate::{calc_creators_fee, TensorError, HUNDRED_PCT, HUNDRED_PCT_BPS};
 
 #[derive(Clone)]
 pub struct MetaplexCore;
@@ -33,6 +34,92 @@ pub struct CoreAsset {
     pub royalty_enforced: bool,
 }
 
+impl CoreAsset {
+    /// Re-checks the invariants of the extracted asset information, for when the struct
+    /// crosses trust boundaries after [`validate_core_asset`]:
+    /// - royalty basis points do not exceed 100%
+    /// - royalty creator percentages, if any, add up to 100%
+    /// - the asset is not its own collection
+    pub fn validate(&self) -> Result<()> {
+        if self.royalty_fee_bps as u64 > HUNDRED_PCT_BPS {
+            msg!("Royalty basis points exceed 100%: {}", self.royalty_fee_bps);
+            return Err(TensorError::InvalidCoreAsset.into());
+        }
+
+        if let Some(creators) = self.royalty_creators.as_ref().filter(|c| !c.is_empty()) {
+            let total = creators
+                .iter()
+                .map(|creator| creator.percentage as u64)
+                .sum::<u64>();
+
+            if total != HUNDRED_PCT {
+                msg!("Royalty creator percentages must add up to 100: {}", total);
+                return Err(TensorError::InvalidCoreAsset.into());
+            }
+        }
+
+        if self.collection == Some(self.pubkey) {
+            msg!("Asset cannot be its own collection");
+            return Err(TensorError::InvalidCoreAsset.into());
+        }
+
+        Ok(())
+    }
+
+    /// Computes the amount owed to each royalty creator for a sale.
+    ///
+    /// The optional `royalty_pct` is ignored when royalties are enforced. The amounts sum
+    /// to the total creators fee: the rounding remainder is paid to the first creator.
+    /// Returns an empty list if the asset has no royalty creators.
+    pub fn royalty_payouts(
+        &self,
+        sale_amount: u64,
+        royalty_pct: Option<u16>,
+    ) -> Result<Vec<(Pubkey, u64)>> {
+        let creators = match &self.royalty_creators {
+            Some(creators) if !creators.is_empty() => creators,
+            _ => return Ok(Vec::new()),
+        };
+
+        let royalty_pct = if self.royalty_enforced {
+            Some(100)
+        } else {
+            royalty_pct
+        };
+        let creators_fee = calc_creators_fee(self.royalty_fee_bps, sale_amount, royalty_pct)?;
+
+        let mut payouts = Vec::with_capacity(creators.len());
+        let mut remaining = creators_fee;
+
+        for creator in creators {
+            let amount = unwrap_checked!({
+                creators_fee
+                    .checked_mul(creator.percentage as u64)?
+                    .checked_div(100)
+            });
+            remaining = unwrap_int!(remaining.checked_sub(amount));
+            payouts.push((creator.address, amount));
+        }
+
+        // Absorb the rounding remainder.
+        payouts[0].1 = unwrap_int!(payouts[0].1.checked_add(remaining));
+
+        Ok(payouts)
+    }
+}
+
+/// Indicates whether royalties are enforced for the transferring program under the royalties
+/// rule set.
+///
+/// Programs on a `ProgramAllowList` are not subject to enforcement; royalties are enforced for
+/// any other program and when there is no rule set.
+pub fn is_royalty_enforced_for(rule_set: &RuleSet, program_id: &Pubkey) -> bool {
+    match rule_set {
+        RuleSet::ProgramAllowList(programs) => !programs.contains(program_id),
+        _ => true,
+    }
+}
+
 /// Validates a mpl-core asset.
 ///
 /// Ensures the asset and collection, if passed in are:
@@ -42,9 +129,33 @@ pub struct CoreAsset {
 /// - collection matches the one stored on the asset
 ///
 /// Extracts royalty and verified creators information from the appropriate plugins.
+///
+/// Royalties are always considered enforced; use [`validate_core_asset_for_program`] to take
+/// the royalties rule set into account.
 pub fn validate_core_asset(
     asset_info: &AccountInfo,
     maybe_collection_info: Option<&AccountInfo>,
+) -> Result<CoreAsset> {
+    validate_core_asset_with_program(asset_info, maybe_collection_info, None)
+}
+
+/// Validates a mpl-core asset transferred by the given program.
+///
+/// Performs the same validation as [`validate_core_asset`], but royalties are only considered
+/// enforced if the program is subject to enforcement under the royalties rule set (see
+/// [`is_royalty_enforced_for`]).
+pub fn validate_core_asset_for_program(
+    asset_info: &AccountInfo,
+    maybe_collection_info: Option<&AccountInfo>,
+    program_id: &Pubkey,
+) -> Result<CoreAsset> {
+    validate_core_asset_with_program(asset_info, maybe_collection_info, Some(program_id))
+}
+
+fn validate_core_asset_with_program(
+    asset_info: &AccountInfo,
+    maybe_collection_info: Option<&AccountInfo>,
+    program_id: Option<&Pubkey>,
 ) -> Result<CoreAsset> {
     // validate the asset account
     assert_ownership(asset_info, Key::AssetV1)?;
@@ -103,13 +214,20 @@ pub fn validate_core_asset(
             .map(|(_, verified_creators, _)| verified_creators.signatures)
             .ok();
 
+    let royalty_enforced = match (&royalties, program_id) {
+        (Some(Royalties { rule_set, .. }), Some(program_id)) => {
+            is_royalty_enforced_for(rule_set, program_id)
+        }
+        _ => true,
+    };
+
     Ok(CoreAsset {
         pubkey: *asset_info.key,
         collection,
         whitelist_creators: verified_creators,
         royalty_creators: royalties.map(|r| r.creators),
         royalty_fee_bps,
-        royalty_enforced: true,
+        royalty_enforced,
     })
 }
 
@@ -119,11 +237,191 @@ pub fn assert_ownership(account: &AccountInfo, discriminator: Key) -> Result<()>
         return Err(TensorError::InvalidCoreAsset.into());
     }
 
-    let data = account.data.borrow();
-
-    if data.len() <= 1 || data[0] != discriminator as u8 {
+    // the account must have data besides the key
+    if account.data_len() <= 1 {
         return Err(TensorError::InvalidCoreAsset.into());
     }
 
-    Ok(())
+    assert_discriminator_u8(account, discriminator as u8)
+        .map_err(|_| TensorError::InvalidCoreAsset.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::account_info;
+
+    fn test_asset(royalty_creators: Option<Vec<Creator>>, royalty_enforced: bool) -> CoreAsset {
+        CoreAsset {
+            pubkey: Pubkey::new_unique(),
+            collection: None,
+            whitelist_creators: None,
+            royalty_creators,
+            royalty_fee_bps: 500,
+            royalty_enforced,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let creators = vec![
+            Creator {
+                address: Pubkey::new_unique(),
+                percentage: 70,
+            },
+            Creator {
+                address: Pubkey::new_unique(),
+                percentage: 30,
+            },
+        ];
+
+        let mut asset = test_asset(Some(creators), true);
+        assert!(asset.validate().is_ok());
+
+        asset.collection = Some(Pubkey::new_unique());
+        assert!(asset.validate().is_ok());
+
+        // no royalty creators
+        assert!(test_asset(None, true).validate().is_ok());
+        assert!(test_asset(Some(vec![]), true).validate().is_ok());
+
+        let invalid_asset = |update: fn(&mut CoreAsset)| {
+            let mut asset = test_asset(Some(vec![]), true);
+            update(&mut asset);
+            asset.validate()
+        };
+
+        for update in [
+            (|asset: &mut CoreAsset| asset.royalty_fee_bps = 10_001) as fn(&mut CoreAsset),
+            |asset| asset.royalty_fee_bps = u16::MAX,
+            |asset| {
+                asset.royalty_creators = Some(vec![Creator {
+                    address: Pubkey::new_unique(),
+                    percentage: 90,
+                }])
+            },
+            |asset| asset.collection = Some(asset.pubkey),
+        ] {
+            assert_eq!(
+                invalid_asset(update),
+                Err(TensorError::InvalidCoreAsset.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_royalty_payouts() {
+        let creators = vec![
+            Creator {
+                address: Pubkey::new_unique(),
+                percentage: 50,
+            },
+            Creator {
+                address: Pubkey::new_unique(),
+                percentage: 30,
+            },
+            Creator {
+                address: Pubkey::new_unique(),
+                percentage: 20,
+            },
+        ];
+        let asset = test_asset(Some(creators.clone()), true);
+
+        // 5% of 1_000_000
+        let payouts = asset.royalty_payouts(1_000_000, None).unwrap();
+        assert_eq!(
+            payouts,
+            vec![
+                (creators[0].address, 25_000),
+                (creators[1].address, 15_000),
+                (creators[2].address, 10_000),
+            ]
+        );
+
+        // Rounding: 5% of 1_079 = 53, split 26 / 15 / 10 plus a remainder of 2.
+        let payouts = asset.royalty_payouts(1_079, None).unwrap();
+        assert_eq!(
+            payouts,
+            vec![
+                (creators[0].address, 28),
+                (creators[1].address, 15),
+                (creators[2].address, 10),
+            ]
+        );
+        assert_eq!(payouts.iter().map(|(_, amount)| amount).sum::<u64>(), 53);
+    }
+
+    #[test]
+    fn test_is_royalty_enforced_for() {
+        let program_id = Pubkey::new_unique();
+
+        // allow-listed
+        let rule_set = RuleSet::ProgramAllowList(vec![Pubkey::new_unique(), program_id]);
+        assert!(!is_royalty_enforced_for(&rule_set, &program_id));
+
+        // not listed
+        let rule_set = RuleSet::ProgramAllowList(vec![Pubkey::new_unique()]);
+        assert!(is_royalty_enforced_for(&rule_set, &program_id));
+        let rule_set = RuleSet::ProgramDenyList(vec![program_id]);
+        assert!(is_royalty_enforced_for(&rule_set, &program_id));
+
+        // no rule set
+        assert!(is_royalty_enforced_for(&RuleSet::None, &program_id));
+    }
+
+    #[test]
+    fn test_royalty_payouts_optional() {
+        let creators = vec![
+            Creator {
+                address: Pubkey::new_unique(),
+                percentage: 33,
+            },
+            Creator {
+                address: Pubkey::new_unique(),
+                percentage: 67,
+            },
+        ];
+        let asset = test_asset(Some(creators.clone()), false);
+
+        // No optional royalties.
+        let payouts = asset.royalty_payouts(1_000_000, None).unwrap();
+        assert_eq!(payouts.iter().map(|(_, amount)| amount).sum::<u64>(), 0);
+
+        // 50% of 5% of 999_999 = 24_999, split 8_249 / 16_749 plus a remainder of 1.
+        let payouts = asset.royalty_payouts(999_999, Some(50)).unwrap();
+        assert_eq!(
+            payouts,
+            vec![(creators[0].address, 8_250), (creators[1].address, 16_749)]
+        );
+
+        // No creators.
+        let asset = test_asset(None, true);
+        assert!(asset.royalty_payouts(1_000_000, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_assert_ownership() {
+        let account = |owner, data| account_info(Pubkey::new_unique(), owner, data);
+
+        assert!(assert_ownership(
+            &account(mpl_core::ID, vec![Key::AssetV1 as u8, 0]),
+            Key::AssetV1
+        )
+        .is_ok());
+
+        for (owner, data) in [
+            // wrong discriminator
+            (mpl_core::ID, vec![Key::CollectionV1 as u8, 0]),
+            // only the key
+            (mpl_core::ID, vec![Key::AssetV1 as u8]),
+            (mpl_core::ID, vec![]),
+            // not owned by the core program
+            (Pubkey::new_unique(), vec![Key::AssetV1 as u8, 0]),
+        ] {
+            assert_eq!(
+                assert_ownership(&account(owner, data), Key::AssetV1),
+                Err(TensorError::InvalidCoreAsset.into())
+            );
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use bytemuck::{Pod, Zeroable};
 
 const DEFAULT_PUBKEY: Pubkey = Pubkey::new_from_array([0u8; 32]);
 
@@ -57,6 +58,35 @@ impl<T: Nullable> NullableOption<T> {
     pub fn none() -> Self {
         Self(T::NONE)
     }
+
+    /// Maps the value with `f` if it is `Some`; a `NONE` value maps to the `NONE` value of `U`.
+    #[inline]
+    pub fn map<U: Nullable>(self, f: impl FnOnce(T) -> U) -> NullableOption<U> {
+        if self.0.is_some() {
+            NullableOption(f(self.0))
+        } else {
+            NullableOption::none()
+        }
+    }
+
+    /// Returns the value if it is `Some`, otherwise `default`.
+    #[inline]
+    pub fn unwrap_or(self, default: T) -> T {
+        if self.0.is_some() {
+            self.0
+        } else {
+            default
+        }
+    }
+
+    /// Inserts `value` if the value is `None`, then returns a mutable reference to the value.
+    #[inline]
+    pub fn get_or_insert(&mut self, value: T) -> &mut T {
+        if self.0.is_none() {
+            self.0 = value;
+        }
+        &mut self.0
+    }
 }
 
 impl<T: Nullable> From<Option<T>> for NullableOption<T> {
@@ -93,6 +123,111 @@ impl_nullable_for_ux!(u64);
 impl_nullable_for_ux!(u128);
 impl_nullable_for_ux!(usize);
 
+/// Signed integers use their minimum value as `NONE`, since `0` is a legitimate value for
+/// signed fields. The caveat is that the minimum value itself cannot be stored as `Some`.
+macro_rules! impl_nullable_for_ix {
+    ($ix:ty) => {
+        impl Nullable for $ix {
+            const NONE: Self = <$ix>::MIN;
+        }
+    };
+}
+
+impl_nullable_for_ix!(i8);
+impl_nullable_for_ix!(i16);
+impl_nullable_for_ix!(i32);
+impl_nullable_for_ix!(i64);
+impl_nullable_for_ix!(i128);
+impl_nullable_for_ix!(isize);
+
+/// 32-byte array (e.g. a hash) where all zeros represent `None`.
+///
+/// It is `Pod`, so it can be stored in zero-copy accounts.
+#[repr(transparent)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Hash32(pub [u8; 32]);
+
+// SAFETY: `Hash32` is a transparent wrapper over a byte array, so it has no padding and any
+// bit pattern (including all zeros) is valid.
+unsafe impl Zeroable for Hash32 {}
+unsafe impl Pod for Hash32 {}
+
+impl Nullable for Hash32 {
+    const NONE: Self = Self([0u8; 32]);
+}
+
+impl From<[u8; 32]> for Hash32 {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+/// Optional boolean flag with a fixed single-byte layout.
+///
+/// Since `false` is a valid value, `bool` cannot be used as its own sentinel. The raw byte is:
+/// - `0`: `None`
+/// - `1`: `Some(false)`
+/// - `2`: `Some(true)`
+///
+/// Any other byte is rejected on deserialization.
+#[repr(transparent)]
+#[derive(AnchorSerialize, Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct NullableBool(u8);
+
+impl NullableBool {
+    const FALSE: u8 = 1;
+    const TRUE: u8 = 2;
+
+    #[inline]
+    pub const fn new(value: bool) -> Self {
+        if value {
+            Self(Self::TRUE)
+        } else {
+            Self(Self::FALSE)
+        }
+    }
+
+    #[inline]
+    pub const fn value(&self) -> Option<bool> {
+        match self.0 {
+            Self::FALSE => Some(false),
+            Self::TRUE => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl AnchorDeserialize for NullableBool {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        match u8::deserialize_reader(reader)? {
+            byte @ (0 | Self::FALSE | Self::TRUE) => Ok(Self(byte)),
+            byte => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid nullable bool: {}", byte),
+            )),
+        }
+    }
+}
+
+impl Nullable for NullableBool {
+    const NONE: Self = Self(0);
+}
+
+impl From<Option<bool>> for NullableBool {
+    fn from(option: Option<bool>) -> Self {
+        match option {
+            Some(value) => Self::new(value),
+            None => Self::NONE,
+        }
+    }
+}
+
+impl From<NullableBool> for Option<bool> {
+    fn from(value: NullableBool) -> Self {
+        value.value()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,4 +271,112 @@ mod tests {
         assert!(!some.is_none());
         assert!(some.is_some());
     }
+
+    #[test]
+    fn test_nullable_ix() {
+        let none = i64::MIN;
+        assert!(none.is_none());
+        assert!(!none.is_some());
+
+        for some in [0i64, -1, 1, i64::MAX, i64::MIN + 1] {
+            assert!(!some.is_none());
+            assert!(some.is_some());
+        }
+    }
+
+    #[test]
+    fn test_nullable_ix_round_trip() {
+        let zero = NullableOption::<i64>::from(Some(0));
+        let data = zero.try_to_vec().unwrap();
+        assert_eq!(data.len(), 8);
+
+        let decoded = NullableOption::<i64>::try_from_slice(&data).unwrap();
+        assert_eq!(decoded.value(), Some(&0));
+
+        let none = NullableOption::<i32>::from(None);
+        let data = none.try_to_vec().unwrap();
+        assert_eq!(data, i32::MIN.to_le_bytes());
+
+        let decoded = NullableOption::<i32>::try_from_slice(&data).unwrap();
+        assert_eq!(decoded.value(), None);
+
+        let negative = NullableOption::<i32>::try_from_slice(&(-5i32).to_le_bytes()).unwrap();
+        assert_eq!(negative.value(), Some(&-5));
+    }
+
+    #[test]
+    fn test_nullable_option_map() {
+        let some = NullableOption::new(42u8);
+        assert_eq!(some.map(u64::from).value(), Some(&42u64));
+        assert_eq!(
+            some.map(|_| Pubkey::new_from_array([1u8; 32])).value(),
+            Some(&Pubkey::new_from_array([1u8; 32]))
+        );
+
+        // mapping a NONE yields the NONE of the target type, without calling `f`
+        let none = NullableOption::<u8>::none();
+        let mapped = none.map(|_| -> i64 { panic!("should not be called") });
+        assert_eq!(mapped, NullableOption::<i64>::none());
+        assert_eq!(mapped.value(), None);
+
+        // mapping to a NONE value yields a NONE
+        assert_eq!(some.map(|_| 0u32).value(), None);
+    }
+
+    #[test]
+    fn test_nullable_option_unwrap_or() {
+        assert_eq!(NullableOption::new(42u64).unwrap_or(7), 42);
+        assert_eq!(NullableOption::<u64>::none().unwrap_or(7), 7);
+        assert_eq!(NullableOption::new(0i32).unwrap_or(7), 0);
+        assert_eq!(NullableOption::<i32>::none().unwrap_or(7), 7);
+    }
+
+    #[test]
+    fn test_nullable_option_get_or_insert() {
+        let mut none = NullableOption::<u16>::none();
+        *none.get_or_insert(5) += 1;
+        assert_eq!(none.value(), Some(&6));
+
+        let mut some = NullableOption::new(42u16);
+        assert_eq!(*some.get_or_insert(5), 42);
+        assert_eq!(some.value(), Some(&42));
+    }
+
+    #[test]
+    fn test_nullable_bool() {
+        // (option, raw byte)
+        for (option, byte) in [(None, 0u8), (Some(false), 1), (Some(true), 2)] {
+            let value = NullableBool::from(option);
+            assert_eq!(value.value(), option);
+            assert_eq!(value.is_none(), option.is_none());
+            assert_eq!(Option::<bool>::from(value), option);
+
+            let data = value.try_to_vec().unwrap();
+            assert_eq!(data, [byte]);
+            assert_eq!(NullableBool::try_from_slice(&data).unwrap(), value);
+        }
+
+        assert_eq!(NullableBool::default(), NullableBool::NONE);
+        assert!(NullableBool::try_from_slice(&[3]).is_err());
+    }
+
+    #[test]
+    fn test_nullable_hash32() {
+        assert_eq!(std::mem::size_of::<Hash32>(), 32);
+        assert_eq!(std::mem::size_of::<NullableOption<Hash32>>(), 32);
+        assert!(Hash32::default().is_none());
+
+        let hash = Hash32::from([7u8; 32]);
+        assert!(hash.is_some());
+        assert_eq!(bytemuck::bytes_of(&hash), &[7u8; 32]);
+
+        for option in [Some(hash), None] {
+            let value = NullableOption::from(option);
+            let data = value.try_to_vec().unwrap();
+            assert_eq!(data.len(), 32);
+
+            let decoded = NullableOption::<Hash32>::try_from_slice(&data).unwrap();
+            assert_eq!(decoded.value(), option.as_ref());
+        }
+    }
 }
@@ -3,8 +3,9 @@ use anchor_lang::prelude::*;
 /// Three-state operation that allows the ability to set, clear or do nothing with a value.
 /// Useful to use in lieu of an Option when the None variant could be ambiguous
 /// about whether to clear or do nothing.
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum Operation<T> {
+    #[default]
     None,
     Clear,
     Set(T),
@@ -36,4 +37,102 @@ impl<T> Operation<T> {
             _ => None,
         }
     }
+
+    /// Maps `Some` to `Set` and `None` to `None`; never produces `Clear`.
+    pub fn from_set_or_none(option: Option<T>) -> Self {
+        match option {
+            Some(value) => Operation::Set(value),
+            None => Operation::None,
+        }
+    }
+
+    /// Applies the operation to an optional value: `Set` replaces it, `Clear` sets it to `None`
+    /// and `None` leaves it untouched.
+    pub fn apply(self, current: &mut Option<T>) {
+        self.apply_to(
+            current,
+            |current, value| *current = Some(value),
+            |current| *current = None,
+        );
+    }
+
+    /// Applies the operation to a value using the given `set` and `clear` functions;
+    /// `None` leaves the value untouched.
+    pub fn apply_to<U>(
+        self,
+        current: &mut U,
+        set: impl FnOnce(&mut U, T),
+        clear: impl FnOnce(&mut U),
+    ) {
+        match self {
+            Operation::None => (),
+            Operation::Clear => clear(current),
+            Operation::Set(value) => set(current, value),
+        }
+    }
+}
+
+impl<T> From<Option<T>> for Operation<T> {
+    fn from(option: Option<T>) -> Self {
+        Self::from_set_or_none(option)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_set_or_none() {
+        assert_eq!(Operation::from_set_or_none(Some(1u8)), Operation::Set(1));
+        assert_eq!(Operation::<u8>::from_set_or_none(None), Operation::None);
+    }
+
+    #[test]
+    fn test_apply() {
+        // (operation, current, expected)
+        for (operation, current, expected) in [
+            (Operation::None, Some(1u8), Some(1)),
+            (Operation::None, None, None),
+            (Operation::Clear, Some(1), None),
+            (Operation::Clear, None, None),
+            (Operation::Set(2), Some(1), Some(2)),
+            (Operation::Set(2), None, Some(2)),
+        ] {
+            let mut current = current;
+            operation.apply(&mut current);
+            assert_eq!(current, expected);
+        }
+    }
+
+    #[test]
+    fn test_apply_to() {
+        let set = |current: &mut u64, value| *current = value;
+        let clear = |current: &mut u64| *current = 0;
+        let mut current = 5u64;
+
+        Operation::None.apply_to(&mut current, set, clear);
+        assert_eq!(current, 5);
+
+        Operation::Set(7).apply_to(&mut current, set, clear);
+        assert_eq!(current, 7);
+
+        Operation::Clear.apply_to(&mut current, set, clear);
+        assert_eq!(current, 0);
+    }
+
+    #[test]
+    fn test_default_and_from() {
+        assert_eq!(Operation::<u8>::default(), Operation::None);
+        // no `Default` bound on the value type
+        struct NoDefault;
+        assert!(Operation::<NoDefault>::default().is_none());
+        assert_eq!(Operation::<u8>::from(None), Operation::default());
+        assert_eq!(Operation::from(Some(3u8)), Operation::Set(3));
+
+        // single discriminant byte for the unit variants
+        assert_eq!(Operation::<u64>::default().try_to_vec().unwrap(), [0]);
+        assert_eq!(Operation::<u64>::Clear.try_to_vec().unwrap(), [1]);
+        assert_eq!(Operation::Set(3u8).try_to_vec().unwrap(), [2, 3]);
+    }
 }
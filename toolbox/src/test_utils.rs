@@ -0,0 +1,93 @@
+use anchor_lang::solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use std::{cell::RefCell, sync::Once};
+
+thread_local! {
+    static INVOKED_INSTRUCTIONS: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+    static INVOKED_SIGNER_SEEDS: RefCell<Vec<Vec<Vec<Vec<u8>>>>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Syscall stubs that provide the clock and rent sysvars and record the instructions (and their
+/// signer seeds) invoked by the current thread.
+struct TestSyscallStubs;
+
+impl SyscallStubs for TestSyscallStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        INVOKED_INSTRUCTIONS.with(|invoked| invoked.borrow_mut().push(instruction.clone()));
+        INVOKED_SIGNER_SEEDS.with(|invoked| {
+            invoked.borrow_mut().push(
+                signers_seeds
+                    .iter()
+                    .map(|seeds| seeds.iter().map(|seed| seed.to_vec()).collect())
+                    .collect(),
+            )
+        });
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Clock) = Clock::default();
+        }
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Rent) = Rent::default();
+        }
+        SUCCESS
+    }
+}
+
+/// Installs the test syscall stubs and clears the instructions recorded by the
+/// current thread.
+pub fn setup_syscall_stubs() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        set_syscall_stubs(Box::new(TestSyscallStubs));
+    });
+    INVOKED_INSTRUCTIONS.with(|invoked| invoked.borrow_mut().clear());
+    INVOKED_SIGNER_SEEDS.with(|invoked| invoked.borrow_mut().clear());
+}
+
+/// Creates a writable account without lamports with the given key, owner and data.
+///
+/// The account memory is leaked, so the account can be used as `AccountInfo<'static>`; set
+/// the lamports with `**account.lamports.borrow_mut() = ...` when needed.
+pub fn account_info(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> AccountInfo<'static> {
+    AccountInfo::new(
+        Box::leak(Box::new(key)),
+        false,
+        true,
+        Box::leak(Box::new(0)),
+        Box::leak(data.into_boxed_slice()),
+        Box::leak(Box::new(owner)),
+        false,
+        0,
+    )
+}
+
+/// Returns the instructions invoked by the current thread since the last call.
+pub fn take_invoked_instructions() -> Vec<Instruction> {
+    INVOKED_INSTRUCTIONS.with(|invoked| invoked.take())
+}
+
+/// Returns the signer seeds of the instructions invoked by the current thread since the last
+/// call, in invocation order.
+pub fn take_invoked_signer_seeds() -> Vec<Vec<Vec<Vec<u8>>>> {
+    INVOKED_SIGNER_SEEDS.with(|invoked| invoked.take())
+}
@@ -7,13 +7,15 @@
 use anchor_lang::{
     solana_program::{
         borsh0_10::try_from_slice_unchecked, program_error::ProgramError, program_pack::Pack,
+        pubkey::Pubkey,
     },
     AnchorDeserialize,
 };
 use anchor_spl::{
     token::spl_token::state::{Account, Multisig},
     token_interface::spl_token_2022::extension::{
-        AccountType, BaseState, Extension, ExtensionType, Length,
+        permanent_delegate::PermanentDelegate, transfer_fee::TransferFeeConfig, AccountType,
+        BaseState, Extension, ExtensionType, Length,
     },
 };
 use bytemuck::Pod;
@@ -37,12 +39,36 @@ pub fn get_extension<V: Extension + Pod>(
         .map_err(|_error| ProgramError::InvalidAccountData)
 }
 
-fn get_extension_bytes<V: Extension>(tlv_data: &[u8]) -> core::result::Result<&[u8], ProgramError> {
+/// Unpack a portion of the TLV data as the desired type, looking up the extension by its
+/// "internal" type.
+///
+/// This allows reading extensions not supported by the current version of the spl-token-2022 crate.
+pub fn get_extension_by_type<V: Pod>(
+    tlv_data: &[u8],
+    extension_type: IExtensionType,
+) -> core::result::Result<&V, ProgramError> {
+    bytemuck::try_from_bytes::<V>(get_extension_bytes_by_type(
+        tlv_data,
+        extension_type as u16,
+    )?)
+    .map_err(|_error| ProgramError::InvalidAccountData)
+}
+
+pub(crate) fn get_extension_bytes<V: Extension>(
+    tlv_data: &[u8],
+) -> core::result::Result<&[u8], ProgramError> {
+    get_extension_bytes_by_type(tlv_data, V::TYPE as u16)
+}
+
+fn get_extension_bytes_by_type(
+    tlv_data: &[u8],
+    extension_type: u16,
+) -> core::result::Result<&[u8], ProgramError> {
     let TlvIndices {
         type_start: _,
         length_start,
         value_start,
-    } = get_extension_indices::<V>(tlv_data)?;
+    } = get_extension_indices(tlv_data, extension_type)?;
     // get_extension_indices has checked that tlv_data is long enough to include these indices
     let length = bytemuck::try_from_bytes::<Length>(&tlv_data[length_start..value_start])
         .map_err(|_error| ProgramError::InvalidAccountData)?;
@@ -53,8 +79,9 @@ fn get_extension_bytes<V: Extension>(tlv_data: &[u8]) -> core::result::Result<&[
     Ok(&tlv_data[value_start..value_end])
 }
 
-fn get_extension_indices<V: Extension>(
+fn get_extension_indices(
     tlv_data: &[u8],
+    extension_type: u16,
 ) -> core::result::Result<TlvIndices, ProgramError> {
     let mut start_index = 0;
     while start_index < tlv_data.len() {
@@ -62,12 +89,15 @@ fn get_extension_indices<V: Extension>(
         if tlv_data.len() < tlv_indices.value_start {
             return Err(ProgramError::InvalidAccountData);
         }
-        let extension_type =
-            ExtensionType::try_from(&tlv_data[tlv_indices.type_start..tlv_indices.length_start]);
+        let current_type = u16::from_le_bytes(
+            (&tlv_data[tlv_indices.type_start..tlv_indices.length_start])
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
         // [FEBO] Make sure we don't bubble the error in case we don't recognize
         // the extension type; the best we can do when we don't recognize the extension is
         // to keep looking for the one we're interested in
-        if extension_type.is_ok() && extension_type.unwrap() == V::TYPE {
+        if current_type == extension_type {
             // found an instance of the extension that we're looking, return!
             return Ok(tlv_indices);
         }
@@ -133,6 +163,41 @@ pub fn get_extension_types(tlv_data: &[u8]) -> Result<Vec<IExtensionType>, Progr
     Ok(extension_types)
 }
 
+/// Checks whether the TLV data contains the given extension type.
+///
+/// Unlike [`get_extension_types`], this does not allocate and stops at the first match. Returns
+/// `false` if the TLV data is malformed before the extension is found.
+pub fn has_extension(tlv_data: &[u8], extension_type: IExtensionType) -> bool {
+    let mut start_index = 0;
+    while start_index < tlv_data.len() {
+        let tlv_indices = get_tlv_indices(start_index);
+        if tlv_data.len() < tlv_indices.length_start {
+            // There aren't enough bytes to store the next type, which means we
+            // got to the end. The last byte could be used during a realloc!
+            return false;
+        }
+        let current_type = u16::from_le_bytes([
+            tlv_data[tlv_indices.type_start],
+            tlv_data[tlv_indices.type_start + 1],
+        ]);
+        if current_type == extension_type as u16 {
+            return true;
+        }
+
+        if tlv_data.len() < tlv_indices.value_start {
+            // not enough bytes to store the length, malformed
+            return false;
+        }
+
+        let length = u16::from_le_bytes([
+            tlv_data[tlv_indices.length_start],
+            tlv_data[tlv_indices.length_start + 1],
+        ]);
+        start_index = tlv_indices.value_start.saturating_add(usize::from(length));
+    }
+    false
+}
+
 pub fn get_variable_len_extension<V: Extension + AnchorDeserialize>(
     tlv_data: &[u8],
 ) -> core::result::Result<V, ProgramError> {
@@ -141,8 +206,41 @@ pub fn get_variable_len_extension<V: Extension + AnchorDeserialize>(
     try_from_slice_unchecked::<V>(data).map_err(|_error| ProgramError::InvalidAccountData)
 }
 
+/// Returns the fee withheld when transferring `amount` tokens in the given epoch, reading the
+/// `TransferFeeConfig` extension from the mint TLV data.
+///
+/// The older fee applies before the epoch of the newer fee, and the newer fee from that epoch
+/// onwards; the fee is capped by the maximum fee. Errors if the extension is not present.
+pub fn get_transfer_fee(tlv_data: &[u8], amount: u64, epoch: u64) -> Result<u64, ProgramError> {
+    get_extension::<TransferFeeConfig>(tlv_data)?
+        .calculate_epoch_fee(epoch, amount)
+        .ok_or(ProgramError::InvalidArgument)
+}
+
+/// Returns the amount received when transferring `amount` tokens in the given epoch, i.e. the
+/// amount minus the transfer fee (see [`get_transfer_fee`]).
+pub fn calculate_post_fee_amount(
+    tlv_data: &[u8],
+    amount: u64,
+    epoch: u64,
+) -> Result<u64, ProgramError> {
+    amount
+        .checked_sub(get_transfer_fee(tlv_data, amount, epoch)?)
+        .ok_or(ProgramError::InvalidArgument)
+}
+
+/// Returns the permanent delegate set on the mint TLV data.
+///
+/// Returns `None` if the mint does not have the `PermanentDelegate` extension or the extension
+/// does not have a delegate set.
+pub fn get_permanent_delegate(tlv_data: &[u8]) -> Option<Pubkey> {
+    get_extension::<PermanentDelegate>(tlv_data)
+        .ok()
+        .and_then(|extension| extension.delegate.into())
+}
+
 #[repr(u16)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum IExtensionType {
     /// [MINT] Includes an optional mint close authority
     MintCloseAuthority = 3,
@@ -294,3 +392,140 @@ const fn adjust_len_for_multisig(account_len: usize) -> usize {
         account_len
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anchor_spl::token_interface::spl_token_2022::{
+        extension::{transfer_fee::TransferFee, BaseStateWithExtensions, StateWithExtensions},
+        state::Mint,
+    };
+
+    use super::*;
+    use crate::token_2022::test_utils::mint_data_with_raw_extensions;
+
+    fn transfer_fee(epoch: u64, maximum_fee: u64, basis_points: u16) -> TransferFee {
+        TransferFee {
+            epoch: epoch.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: basis_points.into(),
+        }
+    }
+
+    /// TLV data of a mint with the given extensions.
+    fn mint_tlv(extensions: &[(u16, &[u8])]) -> Vec<u8> {
+        let data = mint_data_with_raw_extensions(extensions);
+        let mint = StateWithExtensions::<Mint>::unpack(&data).unwrap();
+        mint.get_tlv_data().to_vec()
+    }
+
+    /// TLV data with an unknown extension followed by the transfer fee config.
+    fn transfer_fee_tlv(older: TransferFee, newer: TransferFee) -> Vec<u8> {
+        let config = TransferFeeConfig {
+            older_transfer_fee: older,
+            newer_transfer_fee: newer,
+            ..Default::default()
+        };
+
+        mint_tlv(&[
+            (u16::MAX, &[0, 0]),
+            (
+                ExtensionType::TransferFeeConfig as u16,
+                bytemuck::bytes_of(&config),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_has_extension() {
+        let tlv = mint_tlv(&[
+            (u16::MAX, &[0, 0]),
+            (IExtensionType::PermanentDelegate as u16, &[0; 32]),
+        ]);
+
+        // present, after an unknown extension
+        assert!(has_extension(&tlv, IExtensionType::PermanentDelegate));
+        // absent
+        assert!(!has_extension(&tlv, IExtensionType::TransferHook));
+        assert!(!has_extension(&[], IExtensionType::TransferHook));
+
+        // trailing byte left by a realloc
+        let mut trailing = tlv.clone();
+        trailing.push(0);
+        assert!(has_extension(&trailing, IExtensionType::PermanentDelegate));
+        assert!(!has_extension(&trailing, IExtensionType::TransferHook));
+
+        // type without a length
+        let mut malformed = tlv.clone();
+        malformed.extend((IExtensionType::CpiGuard as u16).to_le_bytes());
+        malformed.push(1);
+        assert!(!has_extension(&malformed, IExtensionType::TransferHook));
+
+        // value past the end of the data: only the first 2 of its 8 bytes are present
+        let value = [(IExtensionType::TransferHook as u16).to_le_bytes(); 4].concat();
+        let mut malformed = mint_tlv(&[(u16::MAX, &value)]);
+        malformed.truncate(6);
+        assert!(!has_extension(&malformed, IExtensionType::TransferHook));
+    }
+
+    #[test]
+    fn test_get_permanent_delegate() {
+        let delegate = Pubkey::new_unique();
+        let extension = PermanentDelegate {
+            delegate: Some(delegate).try_into().unwrap(),
+        };
+        let tlv = mint_tlv(&[(
+            ExtensionType::PermanentDelegate as u16,
+            bytemuck::bytes_of(&extension),
+        )]);
+        assert_eq!(get_permanent_delegate(&tlv), Some(delegate));
+
+        // extension without a delegate
+        let extension = PermanentDelegate::default();
+        let tlv = mint_tlv(&[(
+            ExtensionType::PermanentDelegate as u16,
+            bytemuck::bytes_of(&extension),
+        )]);
+        assert_eq!(get_permanent_delegate(&tlv), None);
+
+        // missing extension
+        let tlv = transfer_fee_tlv(transfer_fee(0, 0, 0), transfer_fee(0, 0, 0));
+        assert_eq!(get_permanent_delegate(&tlv), None);
+    }
+
+    #[test]
+    fn test_get_transfer_fee() {
+        // 1% until epoch 10, 5% from epoch 10, capped at 1_000
+        let tlv = transfer_fee_tlv(transfer_fee(0, u64::MAX, 100), transfer_fee(10, 1_000, 500));
+
+        // older fee, rounded up
+        assert_eq!(get_transfer_fee(&tlv, 10_001, 9).unwrap(), 101);
+        assert_eq!(calculate_post_fee_amount(&tlv, 10_001, 9).unwrap(), 9_900);
+
+        // newer fee from its epoch onwards
+        assert_eq!(get_transfer_fee(&tlv, 10_000, 10).unwrap(), 500);
+        assert_eq!(get_transfer_fee(&tlv, 10_000, 11).unwrap(), 500);
+        assert_eq!(calculate_post_fee_amount(&tlv, 10_000, 10).unwrap(), 9_500);
+
+        // maximum fee cap
+        assert_eq!(get_transfer_fee(&tlv, 1_000_000, 10).unwrap(), 1_000);
+        assert_eq!(
+            calculate_post_fee_amount(&tlv, 1_000_000, 10).unwrap(),
+            999_000
+        );
+        // the older fee has no cap
+        assert_eq!(get_transfer_fee(&tlv, 1_000_000, 9).unwrap(), 10_000);
+
+        // zero amount
+        assert_eq!(get_transfer_fee(&tlv, 0, 10).unwrap(), 0);
+        assert_eq!(calculate_post_fee_amount(&tlv, 0, 10).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_get_transfer_fee_missing_extension() {
+        let tlv = mint_tlv(&[(u16::MAX, &[0, 0])]);
+
+        assert!(get_transfer_fee(&tlv, 100, 0).is_err());
+        assert!(calculate_post_fee_amount(&tlv, 100, 0).is_err());
+        assert!(get_transfer_fee(&[], 100, 0).is_err());
+    }
+}
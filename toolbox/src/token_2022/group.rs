@@ -0,0 +1,293 @@
+//! Token group types and functions for the SPL Token 2022 program.
+//!
+//! The group extensions are not supported by the current version of the spl-token-2022
+//! crate, so their layouts are defined here and read using the "internal" extension types.
+
+use anchor_lang::{
+    solana_program::{account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey},
+    Result,
+};
+use anchor_spl::token_interface::spl_token_2022::{
+    self,
+    extension::{BaseStateWithExtensions, StateWithExtensions},
+    state::Mint,
+};
+use bytemuck::{Pod, Zeroable};
+
+use super::extension::{get_extension_by_type, IExtensionType};
+use crate::Nullable;
+
+/// Group pointer extension data.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GroupPointer {
+    /// Authority that can set the group address.
+    pub authority: Pubkey,
+    /// Account address that holds the group.
+    pub group_address: Pubkey,
+}
+
+/// Group member pointer extension data.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GroupMemberPointer {
+    /// Authority that can set the member address.
+    pub authority: Pubkey,
+    /// Account address that holds the member.
+    pub member_address: Pubkey,
+}
+
+/// Token group member extension data.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenGroupMember {
+    /// The associated mint, used to counter spoofing to be sure that member belongs to a particular mint.
+    pub mint: Pubkey,
+    /// The pubkey of the `TokenGroup`.
+    pub group: Pubkey,
+    /// The member number (little-endian `u32`).
+    pub member_number: [u8; 4],
+}
+
+/// Token group extension data.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenGroup {
+    /// The authority that can sign to update the group.
+    pub update_authority: Pubkey,
+    /// The associated mint, used to counter spoofing to be sure that group belongs to a particular mint.
+    pub mint: Pubkey,
+    /// The current number of group members (little-endian `u32`).
+    pub size: [u8; 4],
+    /// The maximum number of group members (little-endian `u32`).
+    pub max_size: [u8; 4],
+}
+
+// SAFETY: the extension structs are `#[repr(C)]` and only contain byte arrays, so they have
+// no padding and any bit pattern is valid.
+unsafe impl Zeroable for GroupPointer {}
+unsafe impl Pod for GroupPointer {}
+
+unsafe impl Zeroable for GroupMemberPointer {}
+unsafe impl Pod for GroupMemberPointer {}
+
+unsafe impl Zeroable for TokenGroupMember {}
+unsafe impl Pod for TokenGroupMember {}
+
+unsafe impl Zeroable for TokenGroup {}
+unsafe impl Pod for TokenGroup {}
+
+/// Resolves whether a Token 2022 mint is a member of the given group.
+///
+/// The resolution consists of:
+/// - validating that the mint is owned by the Token 2022 program
+/// - reading the `GroupMemberPointer` extension, which must point to the mint itself
+/// - reading the `TokenGroupMember` extension, which must reference the mint and the group account
+/// - validating that the group account is a Token 2022 mint with a `GroupPointer` extension
+///   pointing to itself and a `TokenGroup` extension
+///
+/// Returns `false` if the mint is not a member of the group, including when the member
+/// extension references another mint or group; accounts not owned by the Token 2022 program
+/// and malformed group accounts result in an error.
+pub fn resolve_group_membership(
+    mint_info: &AccountInfo,
+    group_account_info: &AccountInfo,
+) -> Result<bool> {
+    if mint_info.owner != &spl_token_2022::ID {
+        msg!("Mint must be owned by the Token 2022 program");
+        return Err(ProgramError::IllegalOwner.into());
+    }
+
+    let mint_data = &mint_info.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+
+    let member_address = if let Ok(pointer) = get_extension_by_type::<GroupMemberPointer>(
+        mint.get_tlv_data(),
+        IExtensionType::GroupMemberPointer,
+    ) {
+        pointer.member_address
+    } else {
+        msg!("Missing group member pointer extension");
+        return Ok(false);
+    };
+
+    // only group members stored on the mint itself are supported
+    if member_address.is_none() || member_address != *mint_info.key {
+        msg!("Group member pointer extension: member address should be the mint itself");
+        return Ok(false);
+    }
+
+    let member = if let Ok(member) = get_extension_by_type::<TokenGroupMember>(
+        mint.get_tlv_data(),
+        IExtensionType::TokenGroupMember,
+    ) {
+        member
+    } else {
+        msg!("Missing token group member extension");
+        return Ok(false);
+    };
+
+    if member.mint != *mint_info.key {
+        msg!("Token group member extension: mint mismatch");
+        return Ok(false);
+    }
+
+    if member.group != *group_account_info.key {
+        msg!("Token group member extension: group mismatch");
+        return Ok(false);
+    }
+
+    if group_account_info.owner != &spl_token_2022::ID {
+        msg!("Group account must be owned by the Token 2022 program");
+        return Err(ProgramError::IllegalOwner.into());
+    }
+
+    let group_data = &group_account_info.data.borrow();
+    let group_mint = StateWithExtensions::<Mint>::unpack(group_data)?;
+
+    // only groups stored on the group mint itself are supported
+    let group_address = if let Ok(pointer) = get_extension_by_type::<GroupPointer>(
+        group_mint.get_tlv_data(),
+        IExtensionType::GroupPointer,
+    ) {
+        pointer.group_address
+    } else {
+        msg!("Missing group pointer extension");
+        return Err(ProgramError::InvalidAccountData.into());
+    };
+
+    if group_address != *group_account_info.key {
+        msg!("Group pointer extension: group address should be the group mint itself");
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    let group = if let Ok(group) =
+        get_extension_by_type::<TokenGroup>(group_mint.get_tlv_data(), IExtensionType::TokenGroup)
+    {
+        group
+    } else {
+        msg!("Missing token group extension");
+        return Err(ProgramError::InvalidAccountData.into());
+    };
+
+    if group.mint != *group_account_info.key {
+        msg!("Token group extension: mint mismatch");
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    if u32::from_le_bytes(member.member_number) > u32::from_le_bytes(group.size) {
+        msg!("Token group member extension: member number exceeds group size");
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::account_info, token_2022::test_utils::mint_data_with_extensions};
+
+    fn member_data(mint: &Pubkey, group: &Pubkey) -> Vec<u8> {
+        let pointer = GroupMemberPointer {
+            authority: Pubkey::default(),
+            member_address: *mint,
+        };
+        let member = TokenGroupMember {
+            mint: *mint,
+            group: *group,
+            member_number: 1u32.to_le_bytes(),
+        };
+        mint_data_with_extensions(&[
+            (
+                IExtensionType::GroupMemberPointer,
+                bytemuck::bytes_of(&pointer),
+            ),
+            (
+                IExtensionType::TokenGroupMember,
+                bytemuck::bytes_of(&member),
+            ),
+        ])
+    }
+
+    fn group_data(group: &Pubkey) -> Vec<u8> {
+        group_data_with_pointer(group, group)
+    }
+
+    fn group_data_with_pointer(group: &Pubkey, group_address: &Pubkey) -> Vec<u8> {
+        let pointer = GroupPointer {
+            authority: Pubkey::default(),
+            group_address: *group_address,
+        };
+        let token_group = TokenGroup {
+            update_authority: Pubkey::default(),
+            mint: *group,
+            size: 1u32.to_le_bytes(),
+            max_size: 10u32.to_le_bytes(),
+        };
+        mint_data_with_extensions(&[
+            (IExtensionType::GroupPointer, bytemuck::bytes_of(&pointer)),
+            (IExtensionType::TokenGroup, bytemuck::bytes_of(&token_group)),
+        ])
+    }
+
+    #[test]
+    fn test_resolve_group_membership() {
+        let mint = Pubkey::new_unique();
+        let group = Pubkey::new_unique();
+        let mint_info = account_info(mint, spl_token_2022::ID, member_data(&mint, &group));
+        let group_info = account_info(group, spl_token_2022::ID, group_data(&group));
+
+        assert!(resolve_group_membership(&mint_info, &group_info).unwrap());
+    }
+
+    #[test]
+    fn test_resolve_group_membership_mismatch() {
+        let mint = Pubkey::new_unique();
+        let group = Pubkey::new_unique();
+        let other_group = Pubkey::new_unique();
+        let mint_info = account_info(mint, spl_token_2022::ID, member_data(&mint, &group));
+        let group_info = account_info(other_group, spl_token_2022::ID, group_data(&other_group));
+
+        assert!(!resolve_group_membership(&mint_info, &group_info).unwrap());
+    }
+
+    #[test]
+    fn test_resolve_group_membership_member_mint_mismatch() {
+        let group = Pubkey::new_unique();
+        let mint_info = account_info(
+            Pubkey::new_unique(),
+            spl_token_2022::ID,
+            member_data(&Pubkey::new_unique(), &group),
+        );
+        let group_info = account_info(group, spl_token_2022::ID, group_data(&group));
+
+        assert!(!resolve_group_membership(&mint_info, &group_info).unwrap());
+    }
+
+    #[test]
+    fn test_resolve_group_membership_invalid_accounts() {
+        let mint = Pubkey::new_unique();
+        let group = Pubkey::new_unique();
+        let group_info = account_info(group, spl_token_2022::ID, group_data(&group));
+
+        // spoofed mint
+        let spoofed = account_info(mint, Pubkey::new_unique(), member_data(&mint, &group));
+        assert_eq!(
+            resolve_group_membership(&spoofed, &group_info).unwrap_err(),
+            ProgramError::IllegalOwner.into()
+        );
+
+        // group pointer to another account
+        let mint_info = account_info(mint, spl_token_2022::ID, member_data(&mint, &group));
+        let group_info = account_info(
+            group,
+            spl_token_2022::ID,
+            group_data_with_pointer(&group, &Pubkey::new_unique()),
+        );
+        assert_eq!(
+            resolve_group_membership(&mint_info, &group_info).unwrap_err(),
+            ProgramError::InvalidAccountData.into()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 pub mod extension;
+pub mod group;
 pub mod token;
 pub mod transfer;
 pub mod wns;
@@ -10,6 +11,7 @@ use anchor_lang::{
 use anchor_spl::{
     token_2022::spl_token_2022::extension::transfer_hook::TransferHook,
     token_interface::spl_token_2022::{
+        self,
         extension::{BaseStateWithExtensions, StateWithExtensions},
         state::Mint,
     },
@@ -17,7 +19,11 @@ use anchor_spl::{
 use spl_token_metadata_interface::state::TokenMetadata;
 use std::str::FromStr;
 
-use self::extension::{get_extension, get_variable_len_extension};
+use self::extension::{
+    get_extension, get_permanent_delegate, get_variable_len_extension, has_extension,
+    IExtensionType,
+};
+use crate::TensorError;
 
 // Prefix used by Libreplex to identify royalty accounts.
 const LIBREPLEX_RO: &str = "_ro_";
@@ -28,6 +34,9 @@ const LIBREPLEX_TRANSFER_HOOK: Pubkey = Pubkey::new_from_array([
     53, 2, 250, 50, 121, 61, 15, 194, 104, 5, 76,
 ]);
 
+// Seed of the `ExtraAccountMetaList` PDA, as defined by the transfer hook interface.
+const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RoyaltyInfo {
     /// Royalties fee basis points.
@@ -37,6 +46,253 @@ pub struct RoyaltyInfo {
     pub creators: Vec<(Pubkey, u8)>,
 }
 
+/// Merges the royalty information from the mint and its collection.
+///
+/// Royalties defined on the mint take precedence over the ones defined on the collection,
+/// following the same asset-over-collection precedence used for MPL Core assets. The
+/// collection royalties are used only when the mint does not define any.
+pub fn merge_royalty_info(
+    mint_level: Option<RoyaltyInfo>,
+    collection_level: Option<RoyaltyInfo>,
+) -> Option<RoyaltyInfo> {
+    mint_level.or(collection_level)
+}
+
+/// Parses the royalty destination from a Libreplex additional metadata key.
+///
+/// The key must be the destination address prefixed by exactly one `_ro_`.
+fn parse_libreplex_destination(key: &str) -> Result<Pubkey> {
+    let destination = key.strip_prefix(LIBREPLEX_RO).ok_or_else(|| {
+        msg!("[ERROR] Missing royalty destination prefix");
+        ProgramError::InvalidAccountData
+    })?;
+
+    Pubkey::from_str(destination).map_err(|_error| {
+        msg!("[ERROR] Could not parse destination address");
+        ProgramError::InvalidAccountData.into()
+    })
+}
+
+/// Validation of the royalty destinations extracted from the mint metadata.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoyaltyDestinationCheck {
+    /// Destinations are not validated.
+    #[default]
+    None,
+    /// Destinations must not be the default (zero) key.
+    NonZero,
+    /// Destinations must be non-zero on-curve wallets, rejecting PDAs.
+    OnCurve,
+}
+
+/// Asserts that a royalty destination passes the given check.
+pub fn assert_royalty_destination(
+    destination: &Pubkey,
+    check: RoyaltyDestinationCheck,
+) -> Result<()> {
+    let valid = match check {
+        RoyaltyDestinationCheck::None => true,
+        RoyaltyDestinationCheck::NonZero => *destination != Pubkey::default(),
+        // the zero key decodes to a (small order) curve point, so it is checked explicitly
+        RoyaltyDestinationCheck::OnCurve => {
+            *destination != Pubkey::default() && destination.is_on_curve()
+        }
+    };
+
+    if !valid {
+        msg!("[ERROR] Invalid royalty destination: {}", destination);
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    Ok(())
+}
+
+/// Standard used to store the royalty information on the `TokenMetadata` extension of a mint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoyaltyStandard {
+    /// Libreplex royalties: the mint must use the Libreplex transfer hook and the royalties are
+    /// stored as an additional metadata field, with the key being the destination prefixed by
+    /// `_ro_` and the value the seller fee basis points.
+    Libreplex,
+    /// Royalties stored on generic additional metadata fields: the seller fee basis points
+    /// under `seller_fee_key` and the creators under `creators_key`, as a comma-separated list
+    /// of `<address>:<share>` entries with shares adding up to 100.
+    TokenMetadataField {
+        seller_fee_key: &'static str,
+        creators_key: &'static str,
+    },
+}
+
+impl RoyaltyStandard {
+    /// Reads the royalty information of the standard from the mint TLV data.
+    ///
+    /// Returns `None` if the mint does not follow the standard; malformed royalty information
+    /// results in an error.
+    fn read_royalties(
+        &self,
+        tlv_data: &[u8],
+        destination_check: RoyaltyDestinationCheck,
+    ) -> Result<Option<RoyaltyInfo>> {
+        match self {
+            RoyaltyStandard::Libreplex => libreplex_royalties(tlv_data, destination_check),
+            RoyaltyStandard::TokenMetadataField {
+                seller_fee_key,
+                creators_key,
+            } => {
+                metadata_field_royalties(tlv_data, seller_fee_key, creators_key, destination_check)
+            }
+        }
+    }
+}
+
+/// Parses the seller fee basis points from a metadata value.
+fn parse_seller_fee(value: &str) -> Result<u16> {
+    let seller_fee: u16 = value.parse().map_err(|_error| {
+        msg!("[ERROR] Could not parse seller fee");
+        ProgramError::InvalidAccountData
+    })?;
+
+    if seller_fee > 10000 {
+        msg!("[ERROR] Seller fee must be less than or equal to 10000");
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    Ok(seller_fee)
+}
+
+/// Validates that the creator shares add up to 100; an empty list of creators is also valid.
+pub fn validate_creator_shares(creators: &[(Pubkey, u8)]) -> Result<()> {
+    if creators.is_empty() {
+        return Ok(());
+    }
+
+    let total_share: u32 = creators.iter().map(|(_, share)| u32::from(*share)).sum();
+
+    if total_share != 100 {
+        msg!("[ERROR] Creator shares must add up to 100: {}", total_share);
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    Ok(())
+}
+
+/// Parses a comma-separated list of `<address>:<share>` creators.
+fn parse_creators(
+    value: &str,
+    destination_check: RoyaltyDestinationCheck,
+) -> Result<Vec<(Pubkey, u8)>> {
+    let mut creators = vec![];
+
+    for entry in value.split(',') {
+        let (address, share) = entry.trim().split_once(':').ok_or_else(|| {
+            msg!("[ERROR] Could not parse creator entry");
+            ProgramError::InvalidAccountData
+        })?;
+
+        let address = Pubkey::from_str(address).map_err(|_error| {
+            msg!("[ERROR] Could not parse creator address");
+            ProgramError::InvalidAccountData
+        })?;
+        let share: u8 = share.parse().map_err(|_error| {
+            msg!("[ERROR] Could not parse creator share");
+            ProgramError::InvalidAccountData
+        })?;
+
+        assert_royalty_destination(&address, destination_check)?;
+
+        creators.push((address, share));
+    }
+
+    Ok(creators)
+}
+
+/// Reads the Libreplex royalties; only looks for the metadata if the Libreplex hook is in
+/// place to avoid parsing the metadata unnecessarily (or match on a value that it is not
+/// intended to be used).
+fn libreplex_royalties(
+    tlv_data: &[u8],
+    destination_check: RoyaltyDestinationCheck,
+) -> Result<Option<RoyaltyInfo>> {
+    // there is no need to parse the extensions of mints without a transfer hook
+    if !has_extension(tlv_data, IExtensionType::TransferHook) {
+        return Ok(None);
+    }
+
+    let hook_program: Option<Pubkey> =
+        if let Ok(extension) = get_extension::<TransferHook>(tlv_data) {
+            extension.program_id.into()
+        } else {
+            None
+        };
+
+    if hook_program != Some(LIBREPLEX_TRANSFER_HOOK) {
+        return Ok(None);
+    }
+
+    let metadata = if let Ok(metadata) = get_variable_len_extension::<TokenMetadata>(tlv_data) {
+        metadata
+    } else {
+        return Ok(None);
+    };
+
+    let royalties = metadata
+        .additional_metadata
+        .iter()
+        .find(|(key, _)| key.starts_with(LIBREPLEX_RO));
+
+    if let Some((destination, seller_fee)) = royalties {
+        let seller_fee = parse_seller_fee(seller_fee)?;
+
+        let destination = parse_libreplex_destination(destination)?;
+        assert_royalty_destination(&destination, destination_check)?;
+
+        return Ok(Some(RoyaltyInfo {
+            seller_fee,
+            creators: vec![(destination, 100)],
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Reads the royalties stored on generic additional metadata fields.
+fn metadata_field_royalties(
+    tlv_data: &[u8],
+    seller_fee_key: &str,
+    creators_key: &str,
+    destination_check: RoyaltyDestinationCheck,
+) -> Result<Option<RoyaltyInfo>> {
+    let metadata = if let Ok(metadata) = get_variable_len_extension::<TokenMetadata>(tlv_data) {
+        metadata
+    } else {
+        return Ok(None);
+    };
+
+    let field = |key: &str| {
+        metadata
+            .additional_metadata
+            .iter()
+            .find(|(field, _)| field == key)
+            .map(|(_, value)| value)
+    };
+
+    let seller_fee = if let Some(seller_fee) = field(seller_fee_key) {
+        parse_seller_fee(seller_fee)?
+    } else {
+        return Ok(None);
+    };
+
+    let creators = field(creators_key).ok_or_else(|| {
+        msg!("[ERROR] Missing royalty creators");
+        ProgramError::InvalidAccountData
+    })?;
+
+    Ok(Some(RoyaltyInfo {
+        seller_fee,
+        creators: parse_creators(creators, destination_check)?,
+    }))
+}
+
 /// Validates a "vanilla" Token 2022 non-fungible mint account.
 ///
 /// For non-fungibles assets, the validation consists of checking that the mint:
@@ -47,6 +303,34 @@ pub struct RoyaltyInfo {
 /// It also supports Libreplex royalty enforcement by looking for the metadata extension
 /// to retrieve the seller fee basis points and creators.
 pub fn validate_mint(mint_info: &AccountInfo) -> Result<Option<RoyaltyInfo>> {
+    validate_mint_with_destination_check(mint_info, RoyaltyDestinationCheck::None)
+}
+
+/// Validates a "vanilla" Token 2022 non-fungible mint account, as [`validate_mint`], also
+/// validating the royalty destination with the given check.
+///
+/// This prevents sales from failing (or burning royalties) when a mint is configured with
+/// a royalty destination that cannot receive them.
+pub fn validate_mint_with_destination_check(
+    mint_info: &AccountInfo,
+    destination_check: RoyaltyDestinationCheck,
+) -> Result<Option<RoyaltyInfo>> {
+    Ok(
+        validate_mint_with_standards(mint_info, &[RoyaltyStandard::Libreplex], destination_check)?
+            .map(|(_, royalty_info)| royalty_info),
+    )
+}
+
+/// Validates a "vanilla" Token 2022 non-fungible mint account, as
+/// [`validate_mint_with_destination_check`], reading the royalties with the given standards.
+///
+/// The standards are tried in order and the first one the mint follows is returned alongside
+/// its royalty information.
+pub fn validate_mint_with_standards(
+    mint_info: &AccountInfo,
+    standards: &[RoyaltyStandard],
+    destination_check: RoyaltyDestinationCheck,
+) -> Result<Option<(RoyaltyStandard, RoyaltyInfo)>> {
     let mint_data = &mint_info.data.borrow();
     let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
 
@@ -70,48 +354,778 @@ pub fn validate_mint(mint_info: &AccountInfo) -> Result<Option<RoyaltyInfo>> {
         return Err(ProgramError::InvalidAccountData.into());
     }
 
-    let hook_program: Option<Pubkey> =
-        if let Ok(extension) = get_extension::<TransferHook>(mint.get_tlv_data()) {
-            extension.program_id.into()
-        } else {
+    for standard in standards {
+        if let Some(royalty_info) =
+            standard.read_royalties(mint.get_tlv_data(), destination_check)?
+        {
+            validate_creator_shares(&royalty_info.creators)?;
+            return Ok(Some((*standard, royalty_info)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Validates multiple Token 2022 non-fungible mint accounts.
+///
+/// Returns the royalty information of each mint, in the same order as the mints are passed in.
+/// Fails on the first invalid mint, logging its index.
+pub fn validate_mints(mints: &[AccountInfo]) -> Result<Vec<Option<RoyaltyInfo>>> {
+    let mut royalties = Vec::with_capacity(mints.len());
+
+    for (index, mint_info) in mints.iter().enumerate() {
+        match validate_mint(mint_info) {
+            Ok(royalty_info) => royalties.push(royalty_info),
+            Err(error) => {
+                msg!("Invalid mint {} at index {}", mint_info.key, index);
+                return Err(error);
+            }
+        }
+    }
+
+    Ok(royalties)
+}
+
+/// Transfer hook information of a Token 2022 mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferHookInfo {
+    /// Transfer hook program id.
+    pub program_id: Pubkey,
+
+    /// Authority that can set the transfer hook program id.
+    pub authority: Option<Pubkey>,
+
+    /// `ExtraAccountMetaList` PDA of the mint, derived from the transfer hook program.
+    pub extra_account_metas: Pubkey,
+}
+
+/// Asserts that the mint account is owned by the Token 2022 program, so its extensions can
+/// be trusted.
+fn assert_token_2022_mint_owner(mint_info: &AccountInfo) -> Result<()> {
+    if mint_info.owner != &spl_token_2022::ID {
+        msg!("Mint must be owned by the Token 2022 program");
+        return Err(ProgramError::IllegalOwner.into());
+    }
+
+    Ok(())
+}
+
+/// Returns the transfer hook information of a Token 2022 mint, which is required to build a
+/// transfer for a hooked mint.
+///
+/// Returns `None` if the mint does not have the `TransferHook` extension or the extension does
+/// not have a program id set.
+pub fn get_transfer_hook_info(mint_info: &AccountInfo) -> Result<Option<TransferHookInfo>> {
+    assert_token_2022_mint_owner(mint_info)?;
+
+    let mint_data = &mint_info.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+
+    let extension = if let Ok(extension) = get_extension::<TransferHook>(mint.get_tlv_data()) {
+        extension
+    } else {
+        return Ok(None);
+    };
+
+    let program_id: Option<Pubkey> = extension.program_id.into();
+
+    Ok(program_id.map(|program_id| {
+        let (extra_account_metas, _) = Pubkey::find_program_address(
+            &[EXTRA_ACCOUNT_METAS_SEED, mint_info.key.as_ref()],
+            &program_id,
+        );
+
+        TransferHookInfo {
+            program_id,
+            authority: extension.authority.into(),
+            extra_account_metas,
+        }
+    }))
+}
+
+/// Returns the value of a `TokenMetadata` additional metadata field stored on the mint.
+///
+/// Returns `None` if the key is not present and an error if the mint does not have the
+/// `TokenMetadata` extension.
+pub fn get_additional_metadata_field(mint_info: &AccountInfo, key: &str) -> Result<Option<String>> {
+    assert_token_2022_mint_owner(mint_info)?;
+
+    let mint_data = &mint_info.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+
+    let metadata = get_variable_len_extension::<TokenMetadata>(mint.get_tlv_data())?;
+
+    Ok(metadata
+        .additional_metadata
+        .into_iter()
+        .find(|(field, _)| field == key)
+        .map(|(_, value)| value))
+}
+
+/// Returns the update authority of the `TokenMetadata` extension stored on the mint.
+///
+/// Returns `None` if the metadata has no update authority (immutable metadata) and an error
+/// if the mint does not have the `TokenMetadata` extension.
+pub fn get_token_metadata_update_authority(mint_info: &AccountInfo) -> Result<Option<Pubkey>> {
+    assert_token_2022_mint_owner(mint_info)?;
+
+    let mint_data = &mint_info.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+
+    let metadata = get_variable_len_extension::<TokenMetadata>(mint.get_tlv_data())?;
+
+    Ok(metadata.update_authority.into())
+}
+
+/// Asserts that the `TokenMetadata` extension stored on the mint has the expected update
+/// authority.
+pub fn assert_token_metadata_update_authority(
+    mint_info: &AccountInfo,
+    expected_authority: &Pubkey,
+) -> Result<()> {
+    if get_token_metadata_update_authority(mint_info)? != Some(*expected_authority) {
+        msg!("Token metadata extension: update authority mismatch");
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    Ok(())
+}
+
+/// Asserts that the `TokenMetadata` extension stored on the mint has a non-empty name and
+/// symbol.
+///
+/// This is an opt-in listing quality check; it is not part of the mint validation.
+pub fn assert_metadata_nonempty(mint_info: &AccountInfo) -> Result<()> {
+    assert_token_2022_mint_owner(mint_info)?;
+
+    let mint_data = &mint_info.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+
+    let metadata = get_variable_len_extension::<TokenMetadata>(mint.get_tlv_data())?;
+
+    if metadata.name.is_empty() {
+        msg!("Token metadata extension: name must not be empty");
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    if metadata.symbol.is_empty() {
+        msg!("Token metadata extension: symbol must not be empty");
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    Ok(())
+}
+
+/// Asserts that the Token 2022 mint does not have a permanent delegate, which could transfer or
+/// burn tokens held in custody.
+pub fn assert_no_permanent_delegate(mint_info: &AccountInfo) -> Result<()> {
+    assert_token_2022_mint_owner(mint_info)?;
+
+    let mint_data = &mint_info.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+
+    if let Some(delegate) = get_permanent_delegate(mint.get_tlv_data()) {
+        msg!("Mint has a permanent delegate: {}", delegate);
+        return Err(TensorError::PermanentDelegateNotAllowed.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AnchorSerialize;
+    use anchor_spl::token_interface::spl_token_2022::{
+        self,
+        extension::{permanent_delegate::PermanentDelegate, ExtensionType},
+    };
+
+    use super::*;
+    use crate::{test_utils::account_info, token_2022::test_utils::mint_data_with_raw_extensions};
+
+    #[test]
+    fn test_merge_royalty_info() {
+        let mint_level = RoyaltyInfo {
+            seller_fee: 500,
+            creators: vec![(Pubkey::new_unique(), 100)],
+        };
+        let collection_level = RoyaltyInfo {
+            seller_fee: 1000,
+            creators: vec![(Pubkey::new_unique(), 100)],
+        };
+
+        assert_eq!(
+            merge_royalty_info(Some(mint_level.clone()), Some(collection_level.clone())),
+            Some(mint_level.clone())
+        );
+        assert_eq!(
+            merge_royalty_info(Some(mint_level.clone()), None),
+            Some(mint_level)
+        );
+        assert_eq!(
+            merge_royalty_info(None, Some(collection_level.clone())),
+            Some(collection_level)
+        );
+        assert_eq!(merge_royalty_info(None, None), None);
+    }
+
+    #[test]
+    fn test_assert_metadata_nonempty() {
+        let metadata_mint_info = |owner, name: &str, symbol: &str| {
+            let metadata = TokenMetadata {
+                name: name.to_string(),
+                symbol: symbol.to_string(),
+                uri: "https://test.com/1.json".to_string(),
+                ..Default::default()
+            }
+            .try_to_vec()
+            .unwrap();
+            let data =
+                mint_data_with_raw_extensions(&[(ExtensionType::TokenMetadata as u16, &metadata)]);
+
+            account_info(Pubkey::new_unique(), owner, data)
+        };
+
+        assert!(
+            assert_metadata_nonempty(&metadata_mint_info(spl_token_2022::ID, "Test", "TEST"))
+                .is_ok()
+        );
+        // empty name
+        assert!(
+            assert_metadata_nonempty(&metadata_mint_info(spl_token_2022::ID, "", "TEST")).is_err()
+        );
+        // empty symbol
+        assert!(
+            assert_metadata_nonempty(&metadata_mint_info(spl_token_2022::ID, "Test", "")).is_err()
+        );
+        // spoofed mint owned by another program
+        assert_eq!(
+            assert_metadata_nonempty(&metadata_mint_info(Pubkey::new_unique(), "Test", "TEST")),
+            Err(ProgramError::IllegalOwner.into())
+        );
+
+        // missing metadata extension
+        let mint_info = account_info(
+            Pubkey::new_unique(),
+            spl_token_2022::ID,
+            mint_data_with_raw_extensions(&[]),
+        );
+        assert!(assert_metadata_nonempty(&mint_info).is_err());
+    }
+
+    #[test]
+    fn test_assert_no_permanent_delegate() {
+        let extension = PermanentDelegate {
+            delegate: Some(Pubkey::new_unique()).try_into().unwrap(),
+        };
+        let mint_info = account_info(
+            Pubkey::new_unique(),
+            spl_token_2022::ID,
+            mint_data_with_raw_extensions(&[(
+                ExtensionType::PermanentDelegate as u16,
+                bytemuck::bytes_of(&extension),
+            )]),
+        );
+        assert_eq!(
+            assert_no_permanent_delegate(&mint_info).unwrap_err(),
+            TensorError::PermanentDelegateNotAllowed.into()
+        );
+
+        // mint without the extension
+        let mint_info = account_info(
+            Pubkey::new_unique(),
+            spl_token_2022::ID,
+            mint_data_with_raw_extensions(&[]),
+        );
+        assert!(assert_no_permanent_delegate(&mint_info).is_ok());
+
+        // spoofed mint owned by another program
+        let mint_info = account_info(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            mint_data_with_raw_extensions(&[]),
+        );
+        assert_eq!(
+            assert_no_permanent_delegate(&mint_info).unwrap_err(),
+            ProgramError::IllegalOwner.into()
+        );
+    }
+
+    #[test]
+    fn test_get_additional_metadata_field() {
+        let key = Pubkey::new_unique();
+        let metadata = TokenMetadata {
+            mint: key,
+            additional_metadata: vec![
+                ("royalty_basis_points".to_string(), "500".to_string()),
+                ("description".to_string(), "test".to_string()),
+            ],
+            ..Default::default()
+        }
+        .try_to_vec()
+        .unwrap();
+        let mint_info = account_info(
+            key,
+            spl_token_2022::ID,
+            mint_data_with_raw_extensions(&[(ExtensionType::TokenMetadata as u16, &metadata)]),
+        );
+
+        assert_eq!(
+            get_additional_metadata_field(&mint_info, "royalty_basis_points").unwrap(),
+            Some("500".to_string())
+        );
+        assert_eq!(
+            get_additional_metadata_field(&mint_info, "description").unwrap(),
+            Some("test".to_string())
+        );
+        assert_eq!(
+            get_additional_metadata_field(&mint_info, "royalty").unwrap(),
             None
+        );
+
+        // spoofed mint owned by another program
+        let spoofed = account_info(key, Pubkey::new_unique(), mint_info.data.borrow().to_vec());
+        assert_eq!(
+            get_additional_metadata_field(&spoofed, "royalty_basis_points").unwrap_err(),
+            ProgramError::IllegalOwner.into()
+        );
+    }
+
+    #[test]
+    fn test_get_token_metadata_update_authority() {
+        let key = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        for (update_authority, expected) in [(Some(authority), Some(authority)), (None, None)] {
+            let metadata = TokenMetadata {
+                update_authority: update_authority.try_into().unwrap(),
+                mint: key,
+                ..Default::default()
+            }
+            .try_to_vec()
+            .unwrap();
+            let mint_info = account_info(
+                key,
+                spl_token_2022::ID,
+                mint_data_with_raw_extensions(&[(ExtensionType::TokenMetadata as u16, &metadata)]),
+            );
+
+            assert_eq!(
+                get_token_metadata_update_authority(&mint_info).unwrap(),
+                expected
+            );
+            assert_eq!(
+                assert_token_metadata_update_authority(&mint_info, &authority).is_ok(),
+                expected.is_some()
+            );
+            assert!(assert_token_metadata_update_authority(&mint_info, &key).is_err());
+        }
+
+        // Missing metadata extension.
+        let mint_info = account_info(key, spl_token_2022::ID, mint_data_with_raw_extensions(&[]));
+        assert!(get_token_metadata_update_authority(&mint_info).is_err());
+
+        // spoofed mint owned by another program
+        let metadata = TokenMetadata {
+            update_authority: Some(authority).try_into().unwrap(),
+            mint: key,
+            ..Default::default()
+        }
+        .try_to_vec()
+        .unwrap();
+        let spoofed = account_info(
+            key,
+            Pubkey::new_unique(),
+            mint_data_with_raw_extensions(&[(ExtensionType::TokenMetadata as u16, &metadata)]),
+        );
+        assert_eq!(
+            get_token_metadata_update_authority(&spoofed).unwrap_err(),
+            ProgramError::IllegalOwner.into()
+        );
+        assert_eq!(
+            assert_token_metadata_update_authority(&spoofed, &authority).unwrap_err(),
+            ProgramError::IllegalOwner.into()
+        );
+    }
+
+    #[test]
+    fn test_parse_libreplex_destination() {
+        let destination = Pubkey::new_unique();
+
+        assert_eq!(
+            parse_libreplex_destination(&format!("{LIBREPLEX_RO}{destination}")).unwrap(),
+            destination
+        );
+        // doubled prefix is not a valid address
+        assert!(
+            parse_libreplex_destination(&format!("{LIBREPLEX_RO}{LIBREPLEX_RO}{destination}"))
+                .is_err()
+        );
+        // missing prefix
+        assert!(parse_libreplex_destination(&destination.to_string()).is_err());
+    }
+
+    fn libreplex_mint_info(destination: &Pubkey) -> AccountInfo<'static> {
+        let hook = TransferHook {
+            authority: Default::default(),
+            program_id: Some(LIBREPLEX_TRANSFER_HOOK).try_into().unwrap(),
         };
+        let metadata = TokenMetadata {
+            additional_metadata: vec![(format!("{LIBREPLEX_RO}{destination}"), "500".to_string())],
+            ..Default::default()
+        }
+        .try_to_vec()
+        .unwrap();
+        let data = mint_data_with_raw_extensions(&[
+            (
+                ExtensionType::TransferHook as u16,
+                bytemuck::bytes_of(&hook),
+            ),
+            (ExtensionType::TokenMetadata as u16, &metadata),
+        ]);
+
+        account_info(Pubkey::new_unique(), spl_token_2022::ID, data)
+    }
+
+    #[test]
+    fn test_validate_mint_with_destination_check() {
+        // program ids are keypair addresses, so they are on curve
+        let wallet = LIBREPLEX_TRANSFER_HOOK;
+        let (pda, _) = Pubkey::find_program_address(&[b"royalties"], &spl_token_2022::ID);
+        assert!(wallet.is_on_curve());
+
+        // (destination, check, valid)
+        for (destination, check, valid) in [
+            (wallet, RoyaltyDestinationCheck::OnCurve, true),
+            (wallet, RoyaltyDestinationCheck::NonZero, true),
+            (pda, RoyaltyDestinationCheck::NonZero, true),
+            (pda, RoyaltyDestinationCheck::OnCurve, false),
+            (Pubkey::default(), RoyaltyDestinationCheck::None, true),
+            (Pubkey::default(), RoyaltyDestinationCheck::NonZero, false),
+            (Pubkey::default(), RoyaltyDestinationCheck::OnCurve, false),
+        ] {
+            let mint_info = libreplex_mint_info(&destination);
+            let result = validate_mint_with_destination_check(&mint_info, check);
 
-    // currently only Libreplex is supported, but this can be expanded to include
-    // other standards in the future; only looks for the metadata if the correct
-    // hook is in place to avoid parsing the metadata unnecessarily (or match on a
-    // value that it is not intended to be used)
-    if hook_program == Some(LIBREPLEX_TRANSFER_HOOK) {
-        if let Ok(metadata) = get_variable_len_extension::<TokenMetadata>(mint.get_tlv_data()) {
-            let royalties = metadata
-                .additional_metadata
+            if valid {
+                assert_eq!(result.unwrap().unwrap().creators, vec![(destination, 100)]);
+            } else {
+                assert!(result.is_err());
+            }
+        }
+    }
+
+    const METADATA_FIELD_STANDARD: RoyaltyStandard = RoyaltyStandard::TokenMetadataField {
+        seller_fee_key: "royalty_bps",
+        creators_key: "creators",
+    };
+
+    fn metadata_field_mint_info(fields: &[(&str, &str)]) -> AccountInfo<'static> {
+        let metadata = TokenMetadata {
+            additional_metadata: fields
                 .iter()
-                .find(|(key, _)| key.starts_with(LIBREPLEX_RO));
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+        .try_to_vec()
+        .unwrap();
+        let data =
+            mint_data_with_raw_extensions(&[(ExtensionType::TokenMetadata as u16, &metadata)]);
 
-            if let Some((destination, seller_fee)) = royalties {
-                let seller_fee: u16 = seller_fee.parse().map_err(|_error| {
-                    msg!("[ERROR] Could not parse seller fee");
-                    ProgramError::InvalidAccountData
-                })?;
+        account_info(Pubkey::new_unique(), spl_token_2022::ID, data)
+    }
+
+    #[test]
+    fn test_validate_mint_with_standards_libreplex() {
+        let destination = Pubkey::new_unique();
+        let mint_info = libreplex_mint_info(&destination);
+        let expected = RoyaltyInfo {
+            seller_fee: 500,
+            creators: vec![(destination, 100)],
+        };
+
+        for standards in [
+            &[RoyaltyStandard::Libreplex][..],
+            &[METADATA_FIELD_STANDARD, RoyaltyStandard::Libreplex][..],
+        ] {
+            assert_eq!(
+                validate_mint_with_standards(&mint_info, standards, Default::default()).unwrap(),
+                Some((RoyaltyStandard::Libreplex, expected.clone()))
+            );
+        }
+
+        // Libreplex not requested
+        assert_eq!(
+            validate_mint_with_standards(
+                &mint_info,
+                &[METADATA_FIELD_STANDARD],
+                Default::default()
+            )
+            .unwrap(),
+            None
+        );
+    }
 
-                if seller_fee > 10000 {
-                    msg!("[ERROR] Seller fee must be less than or equal to 10000");
-                    return Err(ProgramError::InvalidAccountData.into());
+    #[test]
+    fn test_validate_mint_with_standards_metadata_field() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let creators = format!("{first}:70, {second}:30");
+        let mint_info =
+            metadata_field_mint_info(&[("royalty_bps", "500"), ("creators", &creators)]);
+
+        assert_eq!(
+            validate_mint_with_standards(
+                &mint_info,
+                &[RoyaltyStandard::Libreplex, METADATA_FIELD_STANDARD],
+                Default::default()
+            )
+            .unwrap(),
+            Some((
+                METADATA_FIELD_STANDARD,
+                RoyaltyInfo {
+                    seller_fee: 500,
+                    creators: vec![(first, 70), (second, 30)],
                 }
+            ))
+        );
+        // the default validation only supports Libreplex
+        assert_eq!(validate_mint(&mint_info).unwrap(), None);
+
+        // destination check applies to every creator
+        assert!(validate_mint_with_standards(
+            &metadata_field_mint_info(&[
+                ("royalty_bps", "500"),
+                ("creators", &format!("{first}:70,{}:30", Pubkey::default())),
+            ]),
+            &[METADATA_FIELD_STANDARD],
+            RoyaltyDestinationCheck::NonZero,
+        )
+        .is_err());
+
+        // malformed royalties
+        for fields in [
+            vec![("royalty_bps", "10001"), ("creators", creators.as_str())],
+            vec![("royalty_bps", "500")],
+            vec![("royalty_bps", "500"), ("creators", &format!("{first}:70"))],
+            vec![("royalty_bps", "500"), ("creators", &format!("{first}"))],
+        ] {
+            assert!(validate_mint_with_standards(
+                &metadata_field_mint_info(&fields),
+                &[METADATA_FIELD_STANDARD],
+                Default::default()
+            )
+            .is_err());
+        }
+
+        // no seller fee field
+        assert_eq!(
+            validate_mint_with_standards(
+                &metadata_field_mint_info(&[("creators", &creators)]),
+                &[METADATA_FIELD_STANDARD],
+                Default::default()
+            )
+            .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_creator_shares() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
 
-                let destination = Pubkey::from_str(destination.trim_start_matches(LIBREPLEX_RO))
-                    .map_err(|_error| {
-                        msg!("[ERROR] Could not parse destination address");
-                        ProgramError::InvalidAccountData
-                    })?;
+        assert!(validate_creator_shares(&[(first, 100)]).is_ok());
+        assert!(validate_creator_shares(&[(first, 60), (second, 40)]).is_ok());
+        assert!(validate_creator_shares(&[]).is_ok());
 
-                return Ok(Some(RoyaltyInfo {
-                    seller_fee,
+        assert!(validate_creator_shares(&[(first, 60), (second, 39)]).is_err());
+        assert!(validate_creator_shares(&[(first, 99)]).is_err());
+        // no overflow
+        assert!(validate_creator_shares(&[(first, 255), (second, 101)]).is_err());
+    }
+
+    #[test]
+    fn test_get_transfer_hook_info() {
+        let authority = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let mint_info = libreplex_mint_info(&destination);
+
+        // hook without authority
+        let info = get_transfer_hook_info(&mint_info).unwrap().unwrap();
+        assert_eq!(info.program_id, LIBREPLEX_TRANSFER_HOOK);
+        assert_eq!(info.authority, None);
+        assert_eq!(
+            info.extra_account_metas,
+            Pubkey::find_program_address(
+                &[b"extra-account-metas", mint_info.key.as_ref()],
+                &LIBREPLEX_TRANSFER_HOOK
+            )
+            .0
+        );
+
+        // hook with authority
+        let hook = TransferHook {
+            authority: Some(authority).try_into().unwrap(),
+            program_id: Some(LIBREPLEX_TRANSFER_HOOK).try_into().unwrap(),
+        };
+        let data = mint_data_with_raw_extensions(&[(
+            ExtensionType::TransferHook as u16,
+            bytemuck::bytes_of(&hook),
+        )]);
+        let key = Pubkey::new_unique();
+        let hooked = account_info(key, spl_token_2022::ID, data.clone());
+        let info = get_transfer_hook_info(&hooked).unwrap().unwrap();
+        assert_eq!(info.authority, Some(authority));
+        assert_ne!(info.extra_account_metas, key);
+
+        // spoofed mint
+        let spoofed = account_info(key, Pubkey::new_unique(), data);
+        assert_eq!(
+            get_transfer_hook_info(&spoofed).unwrap_err(),
+            ProgramError::IllegalOwner.into()
+        );
+
+        // hook extension without a program id
+        let hook = TransferHook {
+            authority: Some(authority).try_into().unwrap(),
+            program_id: None.try_into().unwrap(),
+        };
+        let data = mint_data_with_raw_extensions(&[(
+            ExtensionType::TransferHook as u16,
+            bytemuck::bytes_of(&hook),
+        )]);
+        let unset = account_info(key, spl_token_2022::ID, data);
+        assert_eq!(get_transfer_hook_info(&unset).unwrap(), None);
+
+        // no hook extension
+        let vanilla = account_info(key, spl_token_2022::ID, mint_data_with_raw_extensions(&[]));
+        assert_eq!(get_transfer_hook_info(&vanilla).unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_mints() {
+        let destination = Pubkey::new_unique();
+        let mints = [
+            account_info(
+                Pubkey::new_unique(),
+                spl_token_2022::ID,
+                mint_data_with_raw_extensions(&[]),
+            ),
+            libreplex_mint_info(&destination),
+        ];
+
+        assert_eq!(
+            validate_mints(&mints).unwrap(),
+            vec![
+                None,
+                Some(RoyaltyInfo {
+                    seller_fee: 500,
                     creators: vec![(destination, 100)],
-                }));
-            }
+                })
+            ]
+        );
+        assert!(validate_mints(&[]).unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use anchor_lang::solana_program::{
+        program_option::COption, program_pack::Pack, pubkey::Pubkey,
+    };
+    use anchor_spl::token_interface::spl_token_2022::{
+        extension::AccountType,
+        state::{Account, AccountState},
+    };
+
+    use super::{extension::IExtensionType, Mint};
+
+    /// Creates the account data of an initialized NFT mint with the given TLV extensions.
+    pub fn mint_data_with_extensions(extensions: &[(IExtensionType, &[u8])]) -> Vec<u8> {
+        with_extensions(nft_mint_data(), AccountType::Mint, &raw(extensions))
+    }
+
+    /// Creates the account data of an initialized NFT mint with the given TLV extensions,
+    /// identified by their raw extension type (e.g. extensions not in [`IExtensionType`]).
+    ///
+    /// Without extensions, the data is a mint without the account type.
+    pub fn mint_data_with_raw_extensions(extensions: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut data = nft_mint_data();
+
+        if extensions.is_empty() {
+            data.truncate(Mint::LEN);
+            data
+        } else {
+            with_extensions(data, AccountType::Mint, extensions)
         }
     }
 
-    Ok(None)
+    /// Creates the account data of an initialized token account with the given TLV extensions.
+    pub fn token_account_data_with_extensions(
+        mint: &Pubkey,
+        owner: &Pubkey,
+        extensions: &[(IExtensionType, &[u8])],
+    ) -> Vec<u8> {
+        token_account_data_with_raw_extensions(mint, owner, &raw(extensions))
+    }
+
+    /// Creates the account data of an initialized token account with the given TLV extensions,
+    /// identified by their raw extension type.
+    pub fn token_account_data_with_raw_extensions(
+        mint: &Pubkey,
+        owner: &Pubkey,
+        extensions: &[(u16, &[u8])],
+    ) -> Vec<u8> {
+        let mut data = vec![0u8; Account::LEN];
+        Account {
+            mint: *mint,
+            owner: *owner,
+            amount: 1,
+            state: AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+
+        if extensions.is_empty() {
+            data
+        } else {
+            with_extensions(data, AccountType::Account, extensions)
+        }
+    }
+
+    fn nft_mint_data() -> Vec<u8> {
+        let mut data = vec![0u8; Account::LEN];
+        Mint {
+            mint_authority: COption::None,
+            supply: 1,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data[..Mint::LEN]);
+        data
+    }
+
+    fn raw<'a>(extensions: &[(IExtensionType, &'a [u8])]) -> Vec<(u16, &'a [u8])> {
+        extensions
+            .iter()
+            .map(|(extension_type, value)| (*extension_type as u16, *value))
+            .collect()
+    }
+
+    fn with_extensions(
+        mut data: Vec<u8>,
+        account_type: AccountType,
+        extensions: &[(u16, &[u8])],
+    ) -> Vec<u8> {
+        data.push(account_type as u8);
+
+        for (extension_type, value) in extensions {
+            data.extend(extension_type.to_le_bytes());
+            data.extend((value.len() as u16).to_le_bytes());
+            data.extend(*value);
+        }
+
+        data
+    }
 }
@@ -2,23 +2,27 @@ use anchor_lang::{
     context::CpiContext,
     error::ErrorCode,
     solana_program::{
-        account_info::AccountInfo, msg, program_pack::IsInitialized, rent::Rent, sysvar::Sysvar,
+        account_info::AccountInfo, msg, program_pack::IsInitialized, pubkey::Pubkey, rent::Rent,
+        sysvar::Sysvar,
     },
     system_program::create_account,
     Result,
 };
 use anchor_spl::{
-    token_2022::InitializeAccount3,
+    token_2022::{InitializeAccount3, SetAuthority},
     token_interface::{
-        initialize_account3,
+        initialize_account3, set_authority,
         spl_token_2022::{
-            extension::{BaseStateWithExtensions, StateWithExtensions},
+            extension::{cpi_guard::CpiGuard, BaseStateWithExtensions, StateWithExtensions},
+            instruction::AuthorityType,
             state::{Account, Mint},
         },
     },
 };
+use tensor_vipers::assert::is_account_empty;
 
-use super::extension::{get_extension_types, IExtensionType};
+use super::extension::{get_extension, get_extension_types, IExtensionType};
+use crate::SPL_TOKEN_IDS;
 
 /// Struct that holds the accounts required for initializing a token account.
 pub struct InitializeTokenAccount<'a, 'b, 'info> {
@@ -29,6 +33,48 @@ pub struct InitializeTokenAccount<'a, 'b, 'info> {
     pub system_program: &'a AccountInfo<'info>,
     pub token_program: &'a AccountInfo<'info>,
     pub signer_seeds: &'a [&'b [u8]],
+    /// Optional close authority to set on newly created token accounts.
+    pub close_authority: Option<&'a Pubkey>,
+    /// Signer seeds of the `authority`, used to set the close authority when the authority is
+    /// a PDA; empty when the authority is a signer.
+    pub authority_signer_seeds: &'a [&'b [u8]],
+}
+
+impl<'a, 'b, 'info> InitializeTokenAccount<'a, 'b, 'info> {
+    /// Accounts for initializing a token account without a close authority.
+    pub fn new(
+        token_info: &'a AccountInfo<'info>,
+        mint: &'a AccountInfo<'info>,
+        authority: &'a AccountInfo<'info>,
+        payer: &'a AccountInfo<'info>,
+        system_program: &'a AccountInfo<'info>,
+        token_program: &'a AccountInfo<'info>,
+        signer_seeds: &'a [&'b [u8]],
+    ) -> Self {
+        Self {
+            token_info,
+            mint,
+            authority,
+            payer,
+            system_program,
+            token_program,
+            signer_seeds,
+            close_authority: None,
+            authority_signer_seeds: &[],
+        }
+    }
+
+    /// Sets the close authority of the new token account, signing the `SetAuthority` CPI with
+    /// the given signer seeds of the `authority`.
+    pub fn with_close_authority(
+        mut self,
+        close_authority: &'a Pubkey,
+        authority_signer_seeds: &'a [&'b [u8]],
+    ) -> Self {
+        self.close_authority = Some(close_authority);
+        self.authority_signer_seeds = authority_signer_seeds;
+        self
+    }
 }
 
 /// Initializes a token account without checking for the mint extensions.
@@ -40,6 +86,10 @@ pub struct InitializeTokenAccount<'a, 'b, 'info> {
 ///
 /// When `allow_existing` is true, the function will not try to create the account; otherwise, it will
 /// fail if the account already exists. This provides the same functionality as `init_if_needed`.
+///
+/// When a `close_authority` is provided, it is set on the account after it is created. This requires
+/// the `authority` to be a signer or the PDA derived from the `authority_signer_seeds`. The close
+/// authority of existing accounts is not modified.
 pub fn safe_initialize_token_account(
     input: InitializeTokenAccount<'_, '_, '_>,
     allow_existing: bool,
@@ -52,9 +102,7 @@ pub fn safe_initialize_token_account(
     )?);
 
     // Check if the token account is already initialized.
-    if input.token_info.owner == &anchor_lang::solana_program::system_program::ID
-        && input.token_info.lamports() == 0
-    {
+    if is_account_empty(input.token_info) {
         // Determine the size of the account. We cannot deserialize the mint since it might
         // have extensions that are not currently on the version of the spl-token crate being used.
 
@@ -85,6 +133,22 @@ pub fn safe_initialize_token_account(
             },
         );
         initialize_account3(cpi_ctx)?;
+
+        // Set the close authority, if requested.
+        if let Some(close_authority) = input.close_authority {
+            let cpi_ctx = CpiContext::new(
+                input.token_program.clone(),
+                SetAuthority {
+                    current_authority: input.authority.clone(),
+                    account_or_mint: input.token_info.clone(),
+                },
+            );
+            set_authority(
+                cpi_ctx.with_signer(&[input.authority_signer_seeds]),
+                AuthorityType::CloseAccount,
+                Some(*close_authority),
+            )?;
+        }
     } else if allow_existing {
         // Validate that we got the expected token account.
         if input.token_info.owner != input.token_program.key {
@@ -117,3 +181,204 @@ pub fn safe_initialize_token_account(
 
     Ok(())
 }
+
+/// Indicates whether the CPI guard is enabled on a token account.
+///
+/// Privileged operations, such as transfers by the owner, fail when invoked through a CPI
+/// on guarded accounts. Legacy SPL Token accounts and Token 2022 accounts without the
+/// `CpiGuard` extension are never guarded.
+pub fn is_cpi_guard_enabled(token_account_info: &AccountInfo) -> Result<bool> {
+    let token_data = &token_account_info.data.borrow();
+    let token = StateWithExtensions::<Account>::unpack(token_data)?;
+
+    Ok(get_extension::<CpiGuard>(token.get_tlv_data())
+        .map(|extension| extension.lock_cpi.into())
+        .unwrap_or(false))
+}
+
+/// Asserts that a token account is owned by the expected wallet.
+///
+/// Works for both legacy SPL Token and Token 2022 accounts: the account must be owned by one
+/// of the token programs and its `owner` field must match `expected_owner`.
+pub fn assert_token_account_owner(
+    token_account_info: &AccountInfo,
+    expected_owner: &Pubkey,
+) -> Result<()> {
+    if !SPL_TOKEN_IDS.contains(token_account_info.owner) {
+        msg!("Token account must be owned by a token program");
+        return Err(ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+
+    let token_data = &token_account_info.data.borrow();
+    let token = StateWithExtensions::<Account>::unpack(token_data)?;
+
+    if token.base.owner != *expected_owner {
+        msg!("Invalid token owner");
+        return Err(ErrorCode::ConstraintOwner.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_spl::{token::spl_token, token_interface::spl_token_2022};
+
+    use anchor_lang::system_program;
+    use anchor_spl::token_interface::spl_token_2022::instruction::TokenInstruction;
+
+    use super::*;
+    use crate::{
+        test_utils::{
+            account_info, setup_syscall_stubs, take_invoked_instructions, take_invoked_signer_seeds,
+        },
+        token_2022::test_utils::{
+            mint_data_with_raw_extensions, token_account_data_with_extensions,
+        },
+    };
+
+    fn initialize(close_authority: Option<&Pubkey>) -> Vec<TokenInstruction<'static>> {
+        setup_syscall_stubs();
+
+        let token_info = account_info(Pubkey::new_unique(), system_program::ID, vec![]);
+        let mint = account_info(
+            Pubkey::new_unique(),
+            spl_token_2022::ID,
+            mint_data_with_raw_extensions(&[]),
+        );
+        let authority = account_info(Pubkey::new_unique(), system_program::ID, vec![]);
+        let payer = account_info(Pubkey::new_unique(), system_program::ID, vec![]);
+        let system_program = account_info(system_program::ID, Pubkey::default(), vec![]);
+        let token_program = account_info(spl_token_2022::ID, Pubkey::default(), vec![]);
+
+        let input = InitializeTokenAccount::new(
+            &token_info,
+            &mint,
+            &authority,
+            &payer,
+            &system_program,
+            &token_program,
+            &[b"token"],
+        );
+        let input = if let Some(close_authority) = close_authority {
+            input.with_close_authority(close_authority, &[b"authority"])
+        } else {
+            input
+        };
+
+        safe_initialize_token_account(input, false).unwrap();
+
+        take_invoked_instructions()
+            .into_iter()
+            .filter(|instruction| instruction.program_id == spl_token_2022::ID)
+            .map(|instruction| {
+                TokenInstruction::unpack(Box::leak(instruction.data.into_boxed_slice())).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_safe_initialize_token_account_close_authority() {
+        let close_authority = Pubkey::new_unique();
+
+        let instructions = initialize(Some(&close_authority));
+        assert_eq!(instructions.len(), 2);
+        assert!(matches!(
+            instructions[0],
+            TokenInstruction::InitializeAccount3 { .. }
+        ));
+        assert_eq!(
+            instructions[1],
+            TokenInstruction::SetAuthority {
+                authority_type: AuthorityType::CloseAccount,
+                new_authority: Some(close_authority).into(),
+            }
+        );
+
+        // the account is created with its own seeds and the close authority is set with the
+        // authority seeds
+        assert_eq!(
+            take_invoked_signer_seeds(),
+            vec![
+                vec![vec![b"token".to_vec()]],
+                vec![],
+                vec![vec![b"authority".to_vec()]],
+            ]
+        );
+
+        let instructions = initialize(None);
+        assert_eq!(instructions.len(), 1);
+        assert!(matches!(
+            instructions[0],
+            TokenInstruction::InitializeAccount3 { .. }
+        ));
+    }
+
+    #[test]
+    fn test_is_cpi_guard_enabled() {
+        let key = Pubkey::new_unique();
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        for (extensions, expected) in [
+            (vec![(IExtensionType::CpiGuard, &[1u8][..])], true),
+            (vec![(IExtensionType::CpiGuard, &[0u8][..])], false),
+            (vec![(IExtensionType::ImmutableOwner, &[][..])], false),
+            (vec![], false),
+        ] {
+            let token_account_info = account_info(
+                key,
+                spl_token_2022::ID,
+                token_account_data_with_extensions(&mint, &owner, &extensions),
+            );
+
+            assert_eq!(is_cpi_guard_enabled(&token_account_info).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_assert_token_account_owner() {
+        let key = Pubkey::new_unique();
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        for token_program in [spl_token::ID, spl_token_2022::ID] {
+            let token_account_info = account_info(
+                key,
+                token_program,
+                token_account_data_with_extensions(&mint, &owner, &[]),
+            );
+
+            assert!(assert_token_account_owner(&token_account_info, &owner).is_ok());
+            assert_eq!(
+                assert_token_account_owner(&token_account_info, &Pubkey::new_unique()),
+                Err(ErrorCode::ConstraintOwner.into())
+            );
+        }
+
+        // Token 2022 account with extensions.
+        let token_account_info = account_info(
+            key,
+            spl_token_2022::ID,
+            token_account_data_with_extensions(
+                &mint,
+                &owner,
+                &[(IExtensionType::ImmutableOwner, &[])],
+            ),
+        );
+        assert!(assert_token_account_owner(&token_account_info, &owner).is_ok());
+        assert_eq!(
+            assert_token_account_owner(&token_account_info, &mint),
+            Err(ErrorCode::ConstraintOwner.into())
+        );
+
+        // Not owned by a token program.
+        let token_account_info = account_info(
+            key,
+            Pubkey::default(),
+            token_account_data_with_extensions(&mint, &owner, &[]),
+        );
+        assert_eq!(
+            assert_token_account_owner(&token_account_info, &owner),
+            Err(ErrorCode::AccountOwnedByWrongProgram.into())
+        );
+    }
+}
@@ -4,11 +4,49 @@
 
 use anchor_lang::{
     context::CpiContext,
-    solana_program::{instruction::AccountMeta, program::invoke_signed},
+    solana_program::{account_info::AccountInfo, instruction::AccountMeta, program::invoke_signed},
     Result,
 };
 use anchor_spl::token_interface::{spl_token_2022, TransferChecked};
 
+/// Accounts for the `transfer_2022` function.
+pub struct Transfer2022Accounts<'a, 'info> {
+    pub token_program: &'a AccountInfo<'info>,
+    pub from: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub to: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    /// Extra accounts required by the mint extensions (e.g. transfer hook accounts), appended
+    /// to the instruction accounts in order.
+    pub extra_accounts: &'a [AccountInfo<'info>],
+}
+
+/// Transfers tokens with [`transfer_checked`], assembling the `CpiContext` with the extra
+/// accounts and optional signer seeds.
+pub fn transfer_2022(
+    accounts: Transfer2022Accounts,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: Option<&[&[&[u8]]]>,
+) -> Result<()> {
+    let ctx = CpiContext::new(
+        accounts.token_program.clone(),
+        TransferChecked {
+            from: accounts.from.clone(),
+            mint: accounts.mint.clone(),
+            to: accounts.to.clone(),
+            authority: accounts.authority.clone(),
+        },
+    )
+    .with_remaining_accounts(accounts.extra_accounts.to_vec());
+
+    if let Some(signer_seeds) = signer_seeds {
+        transfer_checked(ctx.with_signer(signer_seeds), amount, decimals)
+    } else {
+        transfer_checked(ctx, amount, decimals)
+    }
+}
+
 pub fn transfer_checked<'info>(
     ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
     amount: u64,
@@ -46,3 +84,89 @@ pub fn transfer_checked<'info>(
 
     invoke_signed(&ix, &accounts, ctx.signer_seeds).map_err(Into::into)
 }
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::solana_program::pubkey::Pubkey;
+    use spl_token_2022::instruction::TokenInstruction;
+
+    use super::*;
+    use crate::test_utils::{
+        account_info, setup_syscall_stubs, take_invoked_instructions, take_invoked_signer_seeds,
+    };
+
+    fn token_account(is_writable: bool) -> AccountInfo<'static> {
+        let mut account = account_info(Pubkey::new_unique(), spl_token_2022::ID, vec![]);
+        account.is_writable = is_writable;
+        account
+    }
+
+    #[test]
+    fn test_transfer_2022() {
+        setup_syscall_stubs();
+
+        let mut token_program = account_info(spl_token_2022::ID, spl_token_2022::ID, vec![]);
+        token_program.is_writable = false;
+        token_program.executable = true;
+        let (from, mint, to, authority) = (
+            token_account(true),
+            token_account(false),
+            token_account(true),
+            token_account(false),
+        );
+        let extra_accounts = [token_account(false), token_account(true)];
+        let accounts = || Transfer2022Accounts {
+            token_program: &token_program,
+            from: &from,
+            mint: &mint,
+            to: &to,
+            authority: &authority,
+            extra_accounts: &extra_accounts,
+        };
+
+        // unsigned
+        transfer_2022(accounts(), 10, 2, None).unwrap();
+        // signed
+        let seeds: &[&[u8]] = &[b"escrow", &[255]];
+        transfer_2022(accounts(), 10, 2, Some(&[seeds])).unwrap();
+
+        let instructions = take_invoked_instructions();
+        assert_eq!(instructions.len(), 2);
+
+        for ix in &instructions {
+            assert_eq!(ix.program_id, spl_token_2022::ID);
+            assert_eq!(
+                TokenInstruction::unpack(&ix.data).unwrap(),
+                TokenInstruction::TransferChecked {
+                    amount: 10,
+                    decimals: 2
+                }
+            );
+
+            // base accounts followed by the extra accounts, in order
+            let keys = ix
+                .accounts
+                .iter()
+                .map(|meta| meta.pubkey)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                keys,
+                vec![
+                    *from.key,
+                    *mint.key,
+                    *to.key,
+                    *authority.key,
+                    *extra_accounts[0].key,
+                    *extra_accounts[1].key
+                ]
+            );
+            assert!(!ix.accounts[4].is_writable);
+            assert!(ix.accounts[5].is_writable);
+        }
+
+        assert_eq!(
+            take_invoked_signer_seeds(),
+            vec![vec![], vec![vec![b"escrow".to_vec(), vec![255]]]]
+        );
+    }
+}
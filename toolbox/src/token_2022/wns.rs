@@ -7,14 +7,8 @@
 
 use anchor_lang::{
     solana_program::{
-        account_info::AccountInfo,
-        instruction::{AccountMeta, Instruction},
-        msg,
-        program::invoke_signed,
-        program_error::ProgramError,
-        program_option::COption,
-        pubkey::Pubkey,
-        rent::Rent,
+        account_info::AccountInfo, clock::Clock, instruction::AccountMeta, msg,
+        program_error::ProgramError, program_option::COption, pubkey::Pubkey, rent::Rent,
         sysvar::Sysvar,
     },
     Key, Result,
@@ -30,7 +24,8 @@ use spl_token_metadata_interface::state::TokenMetadata;
 use std::str::FromStr;
 use tensor_vipers::{unwrap_checked, unwrap_int};
 
-use super::extension::{get_extension, get_variable_len_extension};
+use super::extension::{get_extension, get_extension_bytes};
+use crate::{cpi_invoke, pubkey};
 
 anchor_lang::declare_id!("wns1gDLt8fgLcGhWi5MqAqgXpwEP1JftKE9eZnXS1HM");
 
@@ -38,6 +33,15 @@ pub const ROYALTY_BASIS_POINTS_FIELD: &str = "royalty_basis_points";
 
 pub const APPROVE_LEN: usize = 8 + 8;
 
+/// Seed of the WNS approve account PDA.
+pub const APPROVE_ACCOUNT_SEED: &[u8] = b"approve-account";
+
+/// WNS distribution program id.
+pub const DISTRIBUTION_PROGRAM_ID: Pubkey = pubkey("diste3nXmK7ddDTs1zb6uday6j4etCa9RChD8fJ1xay");
+
+/// Anchor discriminator of the WNS approve account.
+const APPROVE_ACCOUNT_DISCRIMINATOR: [u8; 8] = [218, 253, 170, 2, 4, 47, 25, 99];
+
 /// WNS manager account.
 const MANAGER_PUBKEY: Pubkey = Pubkey::new_from_array([
     125, 100, 129, 23, 165, 236, 2, 226, 233, 63, 107, 17, 242, 89, 72, 105, 75, 145, 77, 172, 118,
@@ -190,16 +194,69 @@ pub fn validate_mint(mint_info: &AccountInfo) -> Result<u16> {
         return Err(ProgramError::InvalidAccountData.into());
     }
 
-    let metadata = get_variable_len_extension::<TokenMetadata>(mint.get_tlv_data())?;
-    let royalty_basis_points = metadata
-        .additional_metadata
-        .iter()
-        .find(|(key, _)| key == super::wns::ROYALTY_BASIS_POINTS_FIELD)
-        .map(|(_, value)| value)
-        .map(|value| u16::from_str(value).unwrap())
-        .unwrap_or(0);
+    royalty_bps(mint.get_tlv_data())
+}
+
+/// Returns the WNS royalty basis points of a mint, without deserializing the whole
+/// `TokenMetadata` extension.
+///
+/// The metadata fields are skipped until the `royalty_basis_points` additional metadata field
+/// is found, which avoids allocating the metadata strings. As in [`validate_mint`], the royalty
+/// is `0` when the field is not present. This does not validate the mint.
+pub fn get_wns_royalty_bps(mint_info: &AccountInfo) -> Result<u16> {
+    let mint_data = &mint_info.data.borrow();
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
 
-    Ok(royalty_basis_points)
+    royalty_bps(mint.get_tlv_data())
+}
+
+/// Reads the royalty basis points from the TLV data of a mint.
+fn royalty_bps(tlv_data: &[u8]) -> Result<u16> {
+    let mut metadata = get_extension_bytes::<TokenMetadata>(tlv_data)?;
+
+    // update authority and mint
+    take_bytes(&mut metadata, 64)?;
+    // name, symbol and uri
+    for _ in 0..3 {
+        take_string(&mut metadata)?;
+    }
+
+    let fields = u32::from_le_bytes(take_bytes(&mut metadata, 4)?.try_into().unwrap());
+
+    for _ in 0..fields {
+        let key = take_string(&mut metadata)?;
+        let value = take_string(&mut metadata)?;
+
+        if key == ROYALTY_BASIS_POINTS_FIELD.as_bytes() {
+            return std::str::from_utf8(value)
+                .ok()
+                .and_then(|value| u16::from_str(value).ok())
+                .ok_or_else(|| {
+                    msg!("Invalid royalty basis points");
+                    ProgramError::InvalidAccountData.into()
+                });
+        }
+    }
+
+    Ok(0)
+}
+
+/// Takes `len` bytes from the front of the data.
+fn take_bytes<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+
+    let (bytes, remaining) = data.split_at(len);
+    *data = remaining;
+
+    Ok(bytes)
+}
+
+/// Takes a borsh-serialized string (`u32` length prefix) from the front of the data.
+fn take_string<'a>(data: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let len = u32::from_le_bytes(take_bytes(data, 4)?.try_into().unwrap());
+    take_bytes(data, len as usize)
 }
 
 /// Parameters for the `Approve` helper function.
@@ -228,6 +285,33 @@ impl<'a> ApproveParams<'a> {
     }
 }
 
+/// Estimates the maximum lamports charged to the payer by a WNS approve: the royalty fee, the
+/// rent to fund the approve account and the realloc of the distribution account.
+///
+/// `approve_rent` is the balance of the approve account before the approve. The distribution
+/// account gets realloced based on the creators, so its realloc fee is overestimated as the
+/// rent of 1024 bytes.
+pub fn estimated_approve_fee(royalty_fee: u64, approve_rent: u64) -> Result<u64> {
+    let rent = Rent::get()?;
+
+    // want to account for potential amount paid in rent.
+    // in case WNS tries to drain to approve account, we cap
+    // the rent difference to the minimum rent.
+    let rent_difference = unwrap_int!(std::cmp::max(
+        rent.minimum_balance(APPROVE_LEN),
+        approve_rent,
+    )
+    .checked_sub(approve_rent));
+    // distribution account gets realloced based on creators potentially: overestimate here.
+    let dist_realloc_fee = rent.minimum_balance(1024);
+
+    Ok(unwrap_checked!({
+        royalty_fee
+            .checked_add(rent_difference)?
+            .checked_add(dist_realloc_fee)
+    }))
+}
+
 /// Approves a WNS token transfer.
 ///
 /// This needs to be called before any attempt to transfer a WNS token. For transfers
@@ -246,12 +330,8 @@ pub fn approve(accounts: super::wns::ApproveAccounts, params: ApproveParams) ->
     let mut data = vec![198, 217, 247, 150, 208, 60, 169, 244];
     data.extend(price.to_le_bytes());
 
-    let approve_ix = Instruction {
-        program_id: super::wns::ID,
-        accounts: accounts.to_account_metas(),
-        data,
-    };
-
+    let account_metas = accounts.to_account_metas();
+    let wns_program = accounts.wns_program.clone();
     let payer = accounts.payer.clone();
     let approve = accounts.approve_account.clone();
 
@@ -260,28 +340,19 @@ pub fn approve(accounts: super::wns::ApproveAccounts, params: ApproveParams) ->
     let initial_approve_rent = approve.lamports();
 
     // delegate the fee payment to WNS
-    let result = invoke_signed(&approve_ix, &accounts.to_account_infos(), signer_seeds)
-        .map_err(|error| error.into());
+    let result = cpi_invoke(
+        &wns_program,
+        &super::wns::ID,
+        account_metas,
+        data,
+        &accounts.to_account_infos(),
+        Some(signer_seeds),
+    );
 
     let ending_payer_lamports = payer.lamports();
 
-    // want to account for potential amount paid in rent.
-    // in case WNS tries to drain to approve account, we cap
-    // the rent difference to the minimum rent.
-    let rent_difference = unwrap_int!(std::cmp::max(
-        Rent::get()?.minimum_balance(APPROVE_LEN),
-        initial_approve_rent,
-    )
-    .checked_sub(initial_approve_rent));
-    // distribution account gets realloced based on creators potentially: overestimate here.
-    let dist_realloc_fee = Rent::get()?.minimum_balance(1024);
-
     let payer_difference = unwrap_int!(initial_payer_lamports.checked_sub(ending_payer_lamports));
-    let expected_fee = unwrap_checked!({
-        royalty_fee
-            .checked_add(rent_difference)?
-            .checked_add(dist_realloc_fee)
-    });
+    let expected_fee = estimated_approve_fee(royalty_fee, initial_approve_rent)?;
 
     // assert that payer was charged the expected fee: rent + any royalty fee.
     if payer_difference > expected_fee {
@@ -295,3 +366,323 @@ pub fn approve(accounts: super::wns::ApproveAccounts, params: ApproveParams) ->
 
     result
 }
+
+/// Indicates whether the approve account authorizes a transfer of the mint.
+///
+/// WNS only allows a transfer when the approve account was updated in the current slot, so
+/// the account must be the approve PDA of the mint, owned by the WNS program and hold the
+/// current slot.
+pub fn is_approved(approve_account: &AccountInfo, mint: &Pubkey) -> Result<bool> {
+    let (expected, _) = Pubkey::find_program_address(&[APPROVE_ACCOUNT_SEED, mint.as_ref()], &ID);
+
+    if *approve_account.key != expected {
+        msg!("Invalid approve account");
+        return Err(ProgramError::InvalidSeeds.into());
+    }
+
+    if approve_account.owner != &ID {
+        return Ok(false);
+    }
+
+    let data = approve_account.data.borrow();
+
+    if data.len() < APPROVE_LEN || data[..8] != APPROVE_ACCOUNT_DISCRIMINATOR {
+        return Ok(false);
+    }
+
+    let slot = u64::from_le_bytes(data[8..APPROVE_LEN].try_into().unwrap());
+
+    Ok(slot == Clock::get()?.slot)
+}
+
+/// Derives the WNS distribution account of a collection (group) for a payment mint.
+///
+/// SOL payments use the default pubkey as the payment mint.
+pub fn derive_wns_distribution_account(
+    collection_or_group: &Pubkey,
+    payment_mint: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[collection_or_group.as_ref(), payment_mint.as_ref()],
+        &DISTRIBUTION_PROGRAM_ID,
+    )
+}
+
+/// Asserts that the distribution account is the WNS distribution account of the collection
+/// (group) for the payment mint.
+pub fn assert_wns_distribution_account(
+    distribution_account: &AccountInfo,
+    collection_or_group: &Pubkey,
+    payment_mint: &Pubkey,
+) -> Result<()> {
+    let (expected, _) = derive_wns_distribution_account(collection_or_group, payment_mint);
+
+    if *distribution_account.key != expected {
+        msg!("Invalid distribution account");
+        return Err(ProgramError::InvalidSeeds.into());
+    }
+
+    Ok(())
+}
+
+/// Approves a WNS token transfer, unless the approve account already authorizes it.
+///
+/// This is the idempotent version of [`approve`]: retries in the same slot do not issue the
+/// `approve` CPI again. Returns the lamports charged to the payer, which is `0` when the
+/// transfer was already approved.
+pub fn ensure_wns_approved(accounts: ApproveAccounts, params: ApproveParams) -> Result<u64> {
+    if is_approved(&accounts.approve_account, accounts.mint.key)? {
+        return Ok(0);
+    }
+
+    let payer = accounts.payer.clone();
+    let initial_payer_lamports = payer.lamports();
+
+    approve(accounts, params)?;
+
+    Ok(unwrap_int!(
+        initial_payer_lamports.checked_sub(payer.lamports())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{system_program, AnchorSerialize};
+    use anchor_spl::token_interface::spl_token_2022::{self, extension::ExtensionType};
+
+    use super::*;
+    use crate::test_utils::{account_info, setup_syscall_stubs, take_invoked_instructions};
+    use crate::token_2022::test_utils::mint_data_with_raw_extensions;
+
+    fn approve_data(slot: u64) -> Vec<u8> {
+        let mut data = APPROVE_ACCOUNT_DISCRIMINATOR.to_vec();
+        data.extend(slot.to_le_bytes());
+        data
+    }
+
+    fn approve_accounts(
+        mint: &Pubkey,
+        approve_account: AccountInfo<'static>,
+    ) -> ApproveAccounts<'static> {
+        ApproveAccounts {
+            wns_program: account_info(ID, Pubkey::default(), vec![]),
+            payer: account_info(Pubkey::new_unique(), system_program::ID, vec![]),
+            authority: account_info(Pubkey::new_unique(), system_program::ID, vec![]),
+            mint: account_info(*mint, spl_token_2022::ID, vec![]),
+            approve_account,
+            payment_mint: None,
+            distribution_token_account: None,
+            authority_token_account: None,
+            distribution_account: account_info(Pubkey::new_unique(), ID, vec![]),
+            system_program: account_info(system_program::ID, Pubkey::default(), vec![]),
+            distribution_program: account_info(Pubkey::new_unique(), Pubkey::default(), vec![]),
+            token_program: account_info(spl_token_2022::ID, Pubkey::default(), vec![]),
+            payment_token_program: None,
+        }
+    }
+
+    fn approve_key(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[APPROVE_ACCOUNT_SEED, mint.as_ref()], &ID).0
+    }
+
+    fn wns_mint_info(additional_metadata: Vec<(String, String)>) -> AccountInfo<'static> {
+        let key = Pubkey::new_unique();
+        let pointer = MetadataPointer {
+            authority: Default::default(),
+            metadata_address: Some(key).try_into().unwrap(),
+        };
+        let hook = TransferHook {
+            authority: Default::default(),
+            program_id: Some(ID).try_into().unwrap(),
+        };
+        let metadata = TokenMetadata {
+            mint: key,
+            name: "WNS".to_string(),
+            symbol: "WNS".to_string(),
+            uri: "https://test.com/1.json".to_string(),
+            additional_metadata,
+            ..Default::default()
+        }
+        .try_to_vec()
+        .unwrap();
+
+        account_info(
+            key,
+            spl_token_2022::ID,
+            mint_data_with_raw_extensions(&[
+                (
+                    ExtensionType::MetadataPointer as u16,
+                    bytemuck::bytes_of(&pointer),
+                ),
+                (
+                    ExtensionType::TransferHook as u16,
+                    bytemuck::bytes_of(&hook),
+                ),
+                (ExtensionType::TokenMetadata as u16, &metadata),
+            ]),
+        )
+    }
+
+    #[test]
+    fn test_get_wns_royalty_bps() {
+        let field = |key: &str, value: &str| (key.to_string(), value.to_string());
+
+        for additional_metadata in [
+            vec![field(ROYALTY_BASIS_POINTS_FIELD, "500")],
+            vec![
+                field("creator", "test"),
+                field(ROYALTY_BASIS_POINTS_FIELD, "10000"),
+                field("other", "value"),
+            ],
+            vec![field("creator", "test")],
+            vec![],
+        ] {
+            let mint_info = wns_mint_info(additional_metadata);
+
+            assert_eq!(
+                get_wns_royalty_bps(&mint_info).unwrap(),
+                validate_mint(&mint_info).unwrap()
+            );
+        }
+
+        assert_eq!(
+            get_wns_royalty_bps(&wns_mint_info(vec![field(
+                ROYALTY_BASIS_POINTS_FIELD,
+                "250"
+            )]))
+            .unwrap(),
+            250
+        );
+
+        // invalid value
+        let mint_info = wns_mint_info(vec![field(ROYALTY_BASIS_POINTS_FIELD, "abc")]);
+        assert!(get_wns_royalty_bps(&mint_info).is_err());
+        assert!(validate_mint(&mint_info).is_err());
+
+        // missing metadata extension
+        let mint_info = account_info(
+            Pubkey::new_unique(),
+            spl_token_2022::ID,
+            mint_data_with_raw_extensions(&[]),
+        );
+        assert!(get_wns_royalty_bps(&mint_info).is_err());
+    }
+
+    #[test]
+    fn test_derive_wns_distribution_account() {
+        let group = Pubkey::new_from_array([7u8; 32]);
+        let (distribution, bump) = derive_wns_distribution_account(&group, &Pubkey::default());
+
+        assert_eq!(
+            distribution,
+            pubkey("7Zy2HpqELd4cTYGdNjHg7wsa1N4eVG49xiyrJLDM6kRs")
+        );
+        assert_eq!(bump, 254);
+
+        let distribution_account = account_info(distribution, DISTRIBUTION_PROGRAM_ID, vec![]);
+        assert!(
+            assert_wns_distribution_account(&distribution_account, &group, &Pubkey::default())
+                .is_ok()
+        );
+        // wrong payment mint
+        assert!(assert_wns_distribution_account(
+            &distribution_account,
+            &group,
+            &spl_token_2022::ID
+        )
+        .is_err());
+        // wrong group
+        assert!(assert_wns_distribution_account(
+            &distribution_account,
+            &Pubkey::new_unique(),
+            &Pubkey::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_estimated_approve_fee() {
+        setup_syscall_stubs();
+        let rent = Rent::default();
+        let approve_rent = rent.minimum_balance(APPROVE_LEN);
+        let dist_realloc_fee = rent.minimum_balance(1024);
+
+        // (royalty fee, approve account balance, expected fee)
+        for (royalty_fee, balance, expected) in [
+            // new approve account: the payer funds the full rent
+            (0, 0, approve_rent + dist_realloc_fee),
+            (50_000, 0, 50_000 + approve_rent + dist_realloc_fee),
+            // partially funded approve account
+            (
+                50_000,
+                1_000,
+                50_000 + approve_rent - 1_000 + dist_realloc_fee,
+            ),
+            // existing approve account: no rent
+            (50_000, approve_rent, 50_000 + dist_realloc_fee),
+            (1_000_000, approve_rent * 2, 1_000_000 + dist_realloc_fee),
+        ] {
+            assert_eq!(
+                estimated_approve_fee(royalty_fee, balance).unwrap(),
+                expected
+            );
+        }
+
+        assert!(estimated_approve_fee(u64::MAX, 0).is_err());
+    }
+
+    #[test]
+    fn test_ensure_wns_approved_already_approved() {
+        setup_syscall_stubs();
+        let mint = Pubkey::new_unique();
+        let slot = Clock::get().unwrap().slot;
+        let approve_account = account_info(approve_key(&mint), ID, approve_data(slot));
+
+        assert!(is_approved(&approve_account, &mint).unwrap());
+
+        let fee = ensure_wns_approved(
+            approve_accounts(&mint, approve_account),
+            ApproveParams::no_royalties(),
+        )
+        .unwrap();
+
+        assert_eq!(fee, 0);
+        assert!(take_invoked_instructions().is_empty());
+    }
+
+    #[test]
+    fn test_ensure_wns_approved_not_yet_approved() {
+        setup_syscall_stubs();
+        let mint = Pubkey::new_unique();
+        let slot = Clock::get().unwrap().slot;
+
+        for approve_account in [
+            // not created yet
+            account_info(approve_key(&mint), system_program::ID, vec![]),
+            // approved in a different slot
+            account_info(approve_key(&mint), ID, approve_data(slot + 1)),
+        ] {
+            assert!(!is_approved(&approve_account, &mint).unwrap());
+
+            let fee = ensure_wns_approved(
+                approve_accounts(&mint, approve_account),
+                ApproveParams::no_royalties(),
+            )
+            .unwrap();
+
+            assert_eq!(fee, 0);
+            let instructions = take_invoked_instructions();
+            assert_eq!(instructions.len(), 1);
+            assert_eq!(instructions[0].program_id, ID);
+        }
+
+        // approve account of another mint
+        let approve_account =
+            account_info(approve_key(&Pubkey::new_unique()), ID, approve_data(slot));
+        assert!(ensure_wns_approved(
+            approve_accounts(&mint, approve_account),
+            ApproveParams::no_royalties(),
+        )
+        .is_err());
+    }
+}
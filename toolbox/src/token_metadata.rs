@@ -9,7 +9,7 @@ use anchor_spl::{
 use mpl_token_metadata::{
     accounts::{Edition, MasterEdition, Metadata},
     instructions::{DelegateTransferV1CpiBuilder, TransferV1CpiBuilder},
-    types::{AuthorizationData, Key as MplKey, TokenStandard},
+    types::{AuthorizationData, Key as MplKey, Payload, PayloadType, TokenStandard},
 };
 use tensor_vipers::{throw_err, unwrap_opt};
 
@@ -17,6 +17,9 @@ use crate::TensorError;
 
 pub use mpl_token_metadata::ID;
 
+/// Key of the amount entry in the authorization data payload.
+pub const AMOUNT_PAYLOAD_KEY: &str = "Amount";
+
 #[inline(never)]
 pub fn assert_decode_metadata(mint: &Pubkey, metadata: &AccountInfo) -> Result<Metadata> {
     if *metadata.owner != mpl_token_metadata::ID {
@@ -36,6 +39,31 @@ pub fn assert_decode_metadata(mint: &Pubkey, metadata: &AccountInfo) -> Result<M
     Ok(metadata)
 }
 
+/// Returns the collection key of the metadata only if the collection is verified.
+pub fn get_verified_collection(metadata: &Metadata) -> Option<Pubkey> {
+    metadata
+        .collection
+        .as_ref()
+        .filter(|collection| collection.verified)
+        .map(|collection| collection.key)
+}
+
+/// Asserts that the metadata is of a sized collection parent, i.e. it has collection details.
+///
+/// Use together with [`get_verified_collection`] to validate that an item belongs to a valid
+/// collection, not just any mint that verified it.
+pub fn assert_valid_collection_parent(collection_metadata: &Metadata) -> Result<()> {
+    if collection_metadata.collection_details.is_none() {
+        msg!(
+            "Metadata of mint {} is not a collection parent",
+            collection_metadata.mint
+        );
+        throw_err!(TensorError::InvalidCollection);
+    }
+
+    Ok(())
+}
+
 #[inline(never)]
 pub fn assert_decode_master_edition(edition: &AccountInfo) -> Result<MasterEdition> {
     if *edition.owner != mpl_token_metadata::ID {
@@ -66,6 +94,92 @@ pub fn assert_decode_edition(edition: &AccountInfo) -> Result<Edition> {
     Ok(edition)
 }
 
+/// Decodes a master edition account, validating that it is owned by the Token Metadata program
+/// and that it is the edition PDA of the mint.
+#[inline(never)]
+pub fn decode_master_edition(mint: &Pubkey, edition: &AccountInfo) -> Result<MasterEdition> {
+    assert_edition_pda(mint, edition)?;
+    assert_decode_master_edition(edition)
+}
+
+/// Decodes a print edition account, validating that it is owned by the Token Metadata program
+/// and that it is the edition PDA of the mint.
+#[inline(never)]
+pub fn decode_edition(mint: &Pubkey, edition: &AccountInfo) -> Result<Edition> {
+    assert_edition_pda(mint, edition)?;
+    assert_decode_edition(edition)
+}
+
+/// Returns the number of prints remaining for a master edition, or `None` if the
+/// supply is unlimited.
+pub fn remaining_supply(master_edition: &MasterEdition) -> Option<u64> {
+    master_edition
+        .max_supply
+        .map(|max_supply| max_supply.saturating_sub(master_edition.supply))
+}
+
+fn assert_edition_pda(mint: &Pubkey, edition: &AccountInfo) -> Result<()> {
+    // master and print editions share the same PDA derivation
+    let (expected_edition, _) = MasterEdition::find_pda(mint);
+
+    if *edition.key != expected_edition {
+        throw_err!(TensorError::InvalidEdition);
+    }
+
+    Ok(())
+}
+
+/// Decides whether a mint should be treated as a royalty-bearing NFT.
+///
+/// Mints with an explicit token standard follow it. Legacy NFTs predate the `token_standard`
+/// field, so for mints without a token standard this falls back to the mint supply and
+/// decimals (`1` and `0`) together with the presence of creators on the metadata.
+pub fn treat_as_nft(metadata: &Metadata, supply: u64, decimals: u8) -> bool {
+    match metadata.token_standard {
+        Some(TokenStandard::Fungible) | Some(TokenStandard::FungibleAsset) => false,
+        Some(_) => true,
+        None => {
+            supply == 1
+                && decimals == 0
+                && metadata
+                    .creators
+                    .as_ref()
+                    .is_some_and(|creators| !creators.is_empty())
+        }
+    }
+}
+
+/// Sets the `Amount` payload entry of the authorization data to the transfer amount.
+///
+/// Creates a new authorization data if none is passed in. Fails if the authorization data
+/// already carries an amount that does not match the transfer amount.
+pub fn with_authorization_amount(
+    authorization_data: Option<AuthorizationData>,
+    amount: u64,
+) -> Result<AuthorizationData> {
+    let mut authorization_data = authorization_data.unwrap_or(AuthorizationData {
+        payload: Payload {
+            map: Default::default(),
+        },
+    });
+
+    match authorization_data.payload.map.get(AMOUNT_PAYLOAD_KEY) {
+        Some(PayloadType::Number(value)) if *value == amount => (),
+        Some(_) => {
+            msg!("Authorization data amount does not match the transfer amount");
+            throw_err!(TensorError::InvalidAuthorizationData);
+        }
+        None => {
+            authorization_data
+                .payload
+                .map
+                .insert(AMOUNT_PAYLOAD_KEY.to_string(), PayloadType::Number(amount));
+        }
+    }
+
+    Ok(authorization_data)
+}
+
 /// Transfer Args using AccountInfo types to be more generic.
 pub struct TransferArgsAi<'a, 'info> {
     /// Account that will pay for any associated fees.
@@ -126,9 +240,13 @@ pub struct TransferArgsAi<'a, 'info> {
     ///
     /// If passed, we assign a delegate first, and the call invoke_signed() instead of invoke().
     pub delegate: Option<&'a AccountInfo<'info>>,
+
+    /// Amount of tokens to transfer: `1` for NFTs, or the partial amount of a semi-fungible.
+    pub amount: u64,
 }
 
 /// Transfer Args using Anchor types to be more ergonomic.
+#[derive(Clone)]
 pub struct TransferArgs<'a, 'info> {
     /// Account that will pay for any associated fees.
     pub payer: &'a AccountInfo<'info>,
@@ -188,6 +306,59 @@ pub struct TransferArgs<'a, 'info> {
     ///
     /// If passed, we assign a delegate first, and the call invoke_signed() instead of invoke().
     pub delegate: Option<&'a AccountInfo<'info>>,
+
+    /// Amount of tokens to transfer: `1` for NFTs, or the partial amount of a semi-fungible.
+    pub amount: u64,
+}
+
+/// Builds the [`TransferArgs`] from an Anchor context.
+///
+/// The accounts of the context must follow the `TransferArgs` field names, except for the
+/// token programs, which use the conventional Anchor names:
+/// - `token_program` for the SPL Token program
+/// - `associated_token_program` for the SPL ATA program
+///
+/// Token records, `sysvar_instructions`, `token_metadata_program` and the authorization rules
+/// accounts are expected to be optional accounts. The authorization data and delegate default
+/// to `None` and the amount defaults to `1` unless passed in.
+///
+/// ```ignore
+/// let args = transfer_args_from_ctx!(ctx);
+/// let args = transfer_args_from_ctx!(ctx, authorization_data, Some(&delegate));
+/// let args = transfer_args_from_ctx!(ctx, authorization_data, Some(&delegate), amount);
+/// ```
+#[macro_export]
+macro_rules! transfer_args_from_ctx {
+    ($ctx:expr $(,)?) => {
+        $crate::transfer_args_from_ctx!($ctx, None, None)
+    };
+    ($ctx:expr, $authorization_data:expr, $delegate:expr $(,)?) => {
+        $crate::transfer_args_from_ctx!($ctx, $authorization_data, $delegate, 1)
+    };
+    ($ctx:expr, $authorization_data:expr, $delegate:expr, $amount:expr $(,)?) => {
+        $crate::token_metadata::TransferArgs {
+            payer: &$ctx.accounts.payer,
+            source: &$ctx.accounts.source,
+            source_ata: &$ctx.accounts.source_ata,
+            source_token_record: $ctx.accounts.source_token_record.as_ref(),
+            destination: &$ctx.accounts.destination,
+            destination_ata: &$ctx.accounts.destination_ata,
+            destination_token_record: $ctx.accounts.destination_token_record.as_ref(),
+            mint: &$ctx.accounts.mint,
+            metadata: &$ctx.accounts.metadata,
+            edition: &$ctx.accounts.edition,
+            system_program: &$ctx.accounts.system_program,
+            spl_token_program: &$ctx.accounts.token_program,
+            spl_ata_program: &$ctx.accounts.associated_token_program,
+            sysvar_instructions: $ctx.accounts.sysvar_instructions.as_ref(),
+            token_metadata_program: $ctx.accounts.token_metadata_program.as_ref(),
+            authorization_rules_program: $ctx.accounts.authorization_rules_program.as_ref(),
+            authorization_rules: $ctx.accounts.authorization_rules.as_ref(),
+            authorization_data: $authorization_data,
+            delegate: $delegate,
+            amount: $amount,
+        }
+    };
 }
 
 fn cpi_transfer_ai(args: TransferArgsAi, signer_seeds: Option<&[&[&[u8]]]>) -> Result<()> {
@@ -216,7 +387,7 @@ fn cpi_transfer_ai(args: TransferArgsAi, signer_seeds: Option<&[&[&[u8]]]>) -> R
         .destination_token_record(args.destination_token_record)
         .authorization_rules_program(args.authorization_rules_program)
         .authorization_rules(args.authorization_rules)
-        .amount(1);
+        .amount(args.amount);
 
     // set the authorization data if passed in
     args.authorization_data
@@ -241,7 +412,7 @@ fn cpi_transfer_ai(args: TransferArgsAi, signer_seeds: Option<&[&[&[u8]]]>) -> R
             .token_record(args.source_token_record)
             .authorization_rules(args.authorization_rules)
             .authorization_rules_program(args.authorization_rules_program)
-            .amount(1);
+            .amount(args.amount);
 
         args.authorization_data
             .map(|data| delegate_cpi.authorization_data(data));
@@ -290,7 +461,7 @@ fn cpi_transfer(args: TransferArgs, signer_seeds: Option<&[&[&[u8]]]>) -> Result
                 .map(|account| account.as_ref()),
         )
         .authorization_rules(args.authorization_rules.map(|account| account.as_ref()))
-        .amount(1);
+        .amount(args.amount);
 
     // set the authorization data if passed in
     args.authorization_data
@@ -318,7 +489,7 @@ fn cpi_transfer(args: TransferArgs, signer_seeds: Option<&[&[&[u8]]]>) -> Result
                 args.authorization_rules_program
                     .map(|account| account.as_ref()),
             )
-            .amount(1);
+            .amount(args.amount);
 
         args.authorization_data
             .map(|data| delegate_cpi.authorization_data(data));
@@ -341,6 +512,16 @@ pub fn transfer_with_ai(
     //if passed, use signed_invoke() instead of invoke()
     signer_seeds: Option<&[&[&[u8]]]>,
 ) -> Result<()> {
+    transfer_with_ai_detect(args, signer_seeds).map(|_| ())
+}
+
+/// Transfer a NFT or PNFT using AccountInfos, as [`transfer_with_ai`], returning the token
+/// standard of the metadata, which determines whether the pNFT transfer was used.
+pub fn transfer_with_ai_detect(
+    args: TransferArgsAi,
+    //if passed, use signed_invoke() instead of invoke()
+    signer_seeds: Option<&[&[&[u8]]]>,
+) -> Result<Option<TokenStandard>> {
     let metadata = assert_decode_metadata(&args.mint.key(), args.metadata)?;
 
     if matches!(
@@ -349,7 +530,7 @@ pub fn transfer_with_ai(
             | Some(TokenStandard::ProgrammableNonFungibleEdition)
     ) {
         // pnft transfer
-        return cpi_transfer_ai(args, signer_seeds);
+        return cpi_transfer_ai(args, signer_seeds).map(|_| metadata.token_standard);
     }
 
     // non-pnft / no token std, normal transfer
@@ -364,11 +545,16 @@ pub fn transfer_with_ai(
         },
     );
 
+    // NFTs have 0 decimals, but semi-fungibles need the mint decimals
+    let decimals = Mint::try_deserialize(&mut &args.mint.try_borrow_data()?[..])?.decimals;
+
     if let Some(signer_seeds) = signer_seeds {
-        token_interface::transfer_checked(ctx.with_signer(signer_seeds), 1, 0)
+        token_interface::transfer_checked(ctx.with_signer(signer_seeds), args.amount, decimals)?;
     } else {
-        token_interface::transfer_checked(ctx, 1, 0)
+        token_interface::transfer_checked(ctx, args.amount, decimals)?;
     }
+
+    Ok(metadata.token_standard)
 }
 
 /// Transfer a NFT or PNFT.
@@ -377,7 +563,55 @@ pub fn transfer(
     //if passed, use signed_invoke() instead of invoke()
     signer_seeds: Option<&[&[&[u8]]]>,
 ) -> Result<()> {
+    transfer_detect(args, signer_seeds).map(|_| ())
+}
+
+/// Transfer a NFT or PNFT, as [`transfer`], returning the token standard of the metadata,
+/// which determines whether the pNFT transfer was used.
+pub fn transfer_detect(
+    args: TransferArgs,
+    //if passed, use signed_invoke() instead of invoke()
+    signer_seeds: Option<&[&[&[u8]]]>,
+) -> Result<Option<TokenStandard>> {
     let metadata = assert_decode_metadata(&args.mint.key(), args.metadata)?;
+    transfer_with_metadata(args, &metadata, signer_seeds)?;
+
+    Ok(metadata.token_standard)
+}
+
+/// Transfers multiple NFTs or PNFTs, detecting the token standard of each one.
+///
+/// Stops at the first failed transfer, logging its index and error, and returns
+/// [`TensorError::TransferFailedAtIndex`] with the index as its compared value.
+pub fn transfer_many(
+    args: &[TransferArgs],
+    //if passed, use signed_invoke() instead of invoke()
+    signer_seeds: Option<&[&[&[u8]]]>,
+) -> Result<()> {
+    for (index, transfer_args) in args.iter().enumerate() {
+        if let Err(error) = transfer(transfer_args.clone(), signer_seeds) {
+            msg!("Transfer failed at index {}: {}", index, error);
+            return Err(error!(TensorError::TransferFailedAtIndex).with_values(("index", index)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Transfer a NFT or PNFT using metadata already decoded by the caller, avoiding decoding
+/// the metadata account again.
+///
+/// The metadata is trusted to be the decoded metadata account, e.g. from [`assert_decode_metadata`];
+/// only its mint is checked against the mint being transferred.
+pub fn transfer_with_metadata(
+    args: TransferArgs,
+    metadata: &Metadata,
+    //if passed, use signed_invoke() instead of invoke()
+    signer_seeds: Option<&[&[&[u8]]]>,
+) -> Result<()> {
+    if metadata.mint != args.mint.key() {
+        throw_err!(TensorError::BadMetadata);
+    }
 
     if matches!(
         metadata.token_standard,
@@ -400,9 +634,579 @@ pub fn transfer(
         },
     );
 
+    // NFTs have 0 decimals, but fungible tokens need the mint decimals
+    let decimals = args.mint.decimals;
+
     if let Some(signer_seeds) = signer_seeds {
-        token_interface::transfer_checked(ctx.with_signer(signer_seeds), 1, 0)
+        token_interface::transfer_checked(ctx.with_signer(signer_seeds), args.amount, decimals)
     } else {
-        token_interface::transfer_checked(ctx, 1, 0)
+        token_interface::transfer_checked(ctx, args.amount, decimals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{solana_program::program_pack::Pack, system_program};
+    use anchor_spl::token::spl_token;
+    use mpl_token_metadata::types::{Collection, CollectionDetails, Creator};
+
+    use super::*;
+
+    fn test_metadata() -> Metadata {
+        Metadata {
+            key: MplKey::MetadataV1,
+            update_authority: Pubkey::new_from_array([1u8; 32]),
+            mint: Pubkey::new_from_array([2u8; 32]),
+            name: "Test".to_string(),
+            symbol: "TEST".to_string(),
+            uri: "https://test.com".to_string(),
+            seller_fee_basis_points: 500,
+            creators: None,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: None,
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        }
+    }
+
+    #[test]
+    fn test_get_verified_collection() {
+        let collection = Pubkey::new_from_array([3u8; 32]);
+
+        let mut metadata = test_metadata();
+        metadata.collection = Some(Collection {
+            verified: true,
+            key: collection,
+        });
+        assert_eq!(get_verified_collection(&metadata), Some(collection));
+
+        metadata.collection = Some(Collection {
+            verified: false,
+            key: collection,
+        });
+        assert_eq!(get_verified_collection(&metadata), None);
+
+        metadata.collection = None;
+        assert_eq!(get_verified_collection(&metadata), None);
+    }
+
+    #[test]
+    fn test_assert_valid_collection_parent() {
+        let mut metadata = test_metadata();
+        metadata.collection_details = Some(CollectionDetails::V1 { size: 100 });
+        assert!(assert_valid_collection_parent(&metadata).is_ok());
+
+        // not a collection parent
+        metadata.collection_details = None;
+        assert_eq!(
+            assert_valid_collection_parent(&metadata),
+            Err(TensorError::InvalidCollection.into())
+        );
+    }
+
+    #[test]
+    fn test_decode_master_edition() {
+        let mint = Pubkey::new_unique();
+        let key = MasterEdition::find_pda(&mint).0;
+        let master_edition = MasterEdition {
+            key: MplKey::MasterEditionV2,
+            supply: 5,
+            max_supply: Some(10),
+        };
+        let edition = account_info(
+            key,
+            mpl_token_metadata::ID,
+            false,
+            false,
+            master_edition.try_to_vec().unwrap(),
+        );
+
+        let decoded = decode_master_edition(&mint, edition).unwrap();
+        assert_eq!(decoded, master_edition);
+        assert_eq!(remaining_supply(&decoded), Some(5));
+        // not a print edition
+        assert!(decode_edition(&mint, edition).is_err());
+        // not the edition of another mint
+        assert!(decode_master_edition(&Pubkey::new_unique(), edition).is_err());
+    }
+
+    #[test]
+    fn test_decode_edition() {
+        let mint = Pubkey::new_unique();
+        let key = MasterEdition::find_pda(&mint).0;
+        let print_edition = Edition {
+            key: MplKey::EditionV1,
+            parent: Pubkey::new_unique(),
+            edition: 3,
+        };
+        let edition = account_info(
+            key,
+            mpl_token_metadata::ID,
+            false,
+            false,
+            print_edition.try_to_vec().unwrap(),
+        );
+
+        assert_eq!(decode_edition(&mint, edition).unwrap(), print_edition);
+        // not a master edition
+        assert!(decode_master_edition(&mint, edition).is_err());
+    }
+
+    #[test]
+    fn test_remaining_supply() {
+        let mut master_edition = MasterEdition {
+            key: MplKey::MasterEditionV2,
+            supply: 10,
+            max_supply: Some(10),
+        };
+        assert_eq!(remaining_supply(&master_edition), Some(0));
+
+        master_edition.max_supply = None;
+        assert_eq!(remaining_supply(&master_edition), None);
+    }
+
+    fn account_info(
+        key: Pubkey,
+        owner: Pubkey,
+        is_signer: bool,
+        executable: bool,
+        data: Vec<u8>,
+    ) -> &'static AccountInfo<'static> {
+        let mut account = crate::test_utils::account_info(key, owner, data);
+        account.is_signer = is_signer;
+        account.executable = executable;
+        **account.lamports.borrow_mut() = 1_000_000;
+        Box::leak(Box::new(account))
+    }
+
+    fn token_account_info(mint: &Pubkey, owner: &Pubkey) -> &'static AccountInfo<'static> {
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount: 1,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+
+        account_info(Pubkey::new_unique(), spl_token::ID, false, false, data)
+    }
+
+    struct MockAccounts<'info> {
+        payer: Signer<'info>,
+        source: AccountInfo<'info>,
+        source_ata: InterfaceAccount<'info, TokenAccount>,
+        source_token_record: Option<UncheckedAccount<'info>>,
+        destination: SystemAccount<'info>,
+        destination_ata: InterfaceAccount<'info, TokenAccount>,
+        destination_token_record: Option<UncheckedAccount<'info>>,
+        mint: InterfaceAccount<'info, Mint>,
+        metadata: UncheckedAccount<'info>,
+        edition: UncheckedAccount<'info>,
+        system_program: Program<'info, System>,
+        token_program: Interface<'info, TokenInterface>,
+        associated_token_program: Program<'info, AssociatedToken>,
+        sysvar_instructions: Option<UncheckedAccount<'info>>,
+        token_metadata_program: Option<UncheckedAccount<'info>>,
+        authorization_rules_program: Option<UncheckedAccount<'info>>,
+        authorization_rules: Option<UncheckedAccount<'info>>,
+    }
+
+    struct MockContext<'info> {
+        accounts: MockAccounts<'info>,
+    }
+
+    fn mock_context(source: Pubkey, destination: Pubkey, decimals: u8) -> MockContext<'static> {
+        mock_context_with_standard(source, destination, decimals, None)
+    }
+
+    fn mock_context_with_standard(
+        source: Pubkey,
+        destination: Pubkey,
+        decimals: u8,
+        token_standard: Option<TokenStandard>,
+    ) -> MockContext<'static> {
+        let mut mint_data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            supply: 1,
+            decimals,
+            is_initialized: true,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut mint_data);
+        let mint = account_info(Pubkey::new_unique(), spl_token::ID, false, false, mint_data);
+
+        let metadata = Metadata {
+            mint: *mint.key,
+            token_standard,
+            ..test_metadata()
+        };
+        let metadata = account_info(
+            Metadata::find_pda(mint.key).0,
+            mpl_token_metadata::ID,
+            false,
+            false,
+            metadata.try_to_vec().unwrap(),
+        );
+
+        let unchecked = || {
+            UncheckedAccount::try_from(account_info(
+                Pubkey::new_unique(),
+                mpl_token_metadata::ID,
+                false,
+                false,
+                vec![],
+            ))
+        };
+
+        MockContext {
+            accounts: MockAccounts {
+                payer: Signer::try_from(account_info(
+                    Pubkey::new_unique(),
+                    system_program::ID,
+                    true,
+                    false,
+                    vec![],
+                ))
+                .unwrap(),
+                source: account_info(source, system_program::ID, true, false, vec![]).clone(),
+                source_ata: InterfaceAccount::try_from(token_account_info(mint.key, &source))
+                    .unwrap(),
+                source_token_record: Some(unchecked()),
+                destination: SystemAccount::try_from(account_info(
+                    destination,
+                    system_program::ID,
+                    false,
+                    false,
+                    vec![],
+                ))
+                .unwrap(),
+                destination_ata: InterfaceAccount::try_from(token_account_info(
+                    mint.key,
+                    &destination,
+                ))
+                .unwrap(),
+                destination_token_record: None,
+                mint: InterfaceAccount::try_from(mint).unwrap(),
+                metadata: UncheckedAccount::try_from(metadata),
+                edition: unchecked(),
+                system_program: Program::try_from(account_info(
+                    system_program::ID,
+                    Pubkey::default(),
+                    false,
+                    true,
+                    vec![],
+                ))
+                .unwrap(),
+                token_program: Interface::try_from(account_info(
+                    spl_token::ID,
+                    Pubkey::default(),
+                    false,
+                    true,
+                    vec![],
+                ))
+                .unwrap(),
+                associated_token_program: Program::try_from(account_info(
+                    anchor_spl::associated_token::ID,
+                    Pubkey::default(),
+                    false,
+                    true,
+                    vec![],
+                ))
+                .unwrap(),
+                sysvar_instructions: Some(unchecked()),
+                token_metadata_program: Some(unchecked()),
+                authorization_rules_program: None,
+                authorization_rules: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_transfer_args_from_ctx() {
+        let (source, destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ctx = mock_context(source, destination, 0);
+        let mint = ctx.accounts.mint.to_account_info();
+
+        let args = transfer_args_from_ctx!(ctx);
+        assert_eq!(args.payer.key, ctx.accounts.payer.key);
+        assert_eq!(*args.source.key, source);
+        assert_eq!(args.source_ata.owner, source);
+        assert_eq!(
+            args.source_token_record.map(|account| account.key()),
+            ctx.accounts
+                .source_token_record
+                .as_ref()
+                .map(|account| account.key())
+        );
+        assert_eq!(*args.destination.key, destination);
+        assert_eq!(args.destination_ata.owner, destination);
+        assert!(args.destination_token_record.is_none());
+        assert_eq!(args.mint.key(), *mint.key);
+        assert_eq!(args.metadata.key(), ctx.accounts.metadata.key());
+        assert_eq!(args.edition.key(), ctx.accounts.edition.key());
+        assert_eq!(args.system_program.key(), system_program::ID);
+        assert_eq!(args.spl_token_program.key(), spl_token::ID);
+        assert_eq!(args.spl_ata_program.key(), anchor_spl::associated_token::ID);
+        assert!(args.sysvar_instructions.is_some());
+        assert!(args.token_metadata_program.is_some());
+        assert!(args.authorization_rules_program.is_none());
+        assert!(args.authorization_rules.is_none());
+        assert!(args.authorization_data.is_none());
+        assert!(args.delegate.is_none());
+        assert_eq!(args.amount, 1);
+
+        let delegate = account_info(
+            Pubkey::new_unique(),
+            system_program::ID,
+            true,
+            false,
+            vec![],
+        );
+        let args = transfer_args_from_ctx!(
+            ctx,
+            Some(with_authorization_amount(None, 1).unwrap()),
+            Some(delegate)
+        );
+        assert!(args.authorization_data.is_some());
+        assert_eq!(args.delegate.unwrap().key, delegate.key);
+        assert_eq!(args.amount, 1);
+
+        let args = transfer_args_from_ctx!(ctx, None, None, 5);
+        assert_eq!(args.amount, 5);
+    }
+
+    #[test]
+    fn test_transfer_uses_mint_decimals() {
+        crate::test_utils::setup_syscall_stubs();
+
+        for decimals in [0, 6, 9] {
+            let ctx = mock_context(Pubkey::new_unique(), Pubkey::new_unique(), decimals);
+            transfer(transfer_args_from_ctx!(ctx), None).unwrap();
+
+            let invoked = crate::test_utils::take_invoked_instructions();
+            assert_eq!(invoked.len(), 1);
+            assert_eq!(invoked[0].program_id, spl_token::ID);
+            assert_eq!(
+                spl_token::instruction::TokenInstruction::unpack(&invoked[0].data).unwrap(),
+                spl_token::instruction::TokenInstruction::TransferChecked {
+                    amount: 1,
+                    decimals
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_transfer_amount() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let ctx = mock_context(Pubkey::new_unique(), Pubkey::new_unique(), 2);
+        transfer(transfer_args_from_ctx!(ctx, None, None, 5), None).unwrap();
+
+        let invoked = crate::test_utils::take_invoked_instructions();
+        assert_eq!(invoked.len(), 1);
+        assert_eq!(
+            spl_token::instruction::TokenInstruction::unpack(&invoked[0].data).unwrap(),
+            spl_token::instruction::TokenInstruction::TransferChecked {
+                amount: 5,
+                decimals: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_transfer_with_ai_amount() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let ctx = mock_context(Pubkey::new_unique(), Pubkey::new_unique(), 2);
+        let accounts = &ctx.accounts;
+        let args = TransferArgsAi {
+            payer: accounts.payer.as_ref(),
+            source: &accounts.source,
+            source_ata: accounts.source_ata.as_ref(),
+            source_token_record: None,
+            destination: accounts.destination.as_ref(),
+            destination_ata: accounts.destination_ata.as_ref(),
+            destination_token_record: None,
+            mint: accounts.mint.as_ref(),
+            metadata: accounts.metadata.as_ref(),
+            edition: accounts.edition.as_ref(),
+            system_program: accounts.system_program.as_ref(),
+            spl_token_program: accounts.token_program.as_ref(),
+            spl_ata_program: accounts.associated_token_program.as_ref(),
+            sysvar_instructions: None,
+            token_metadata_program: None,
+            authorization_rules_program: None,
+            authorization_rules: None,
+            authorization_data: None,
+            delegate: None,
+            amount: 5,
+        };
+        transfer_with_ai(args, None).unwrap();
+
+        let invoked = crate::test_utils::take_invoked_instructions();
+        assert_eq!(invoked.len(), 1);
+        assert_eq!(
+            spl_token::instruction::TokenInstruction::unpack(&invoked[0].data).unwrap(),
+            spl_token::instruction::TokenInstruction::TransferChecked {
+                amount: 5,
+                decimals: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_transfer_with_metadata() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let ctx = mock_context(Pubkey::new_unique(), Pubkey::new_unique(), 0);
+        let metadata =
+            assert_decode_metadata(&ctx.accounts.mint.key(), &ctx.accounts.metadata).unwrap();
+
+        transfer_with_metadata(transfer_args_from_ctx!(ctx), &metadata, None).unwrap();
+        assert_eq!(crate::test_utils::take_invoked_instructions().len(), 1);
+
+        // metadata of another mint
+        let other = Metadata {
+            mint: Pubkey::new_unique(),
+            ..metadata
+        };
+        assert_eq!(
+            transfer_with_metadata(transfer_args_from_ctx!(ctx), &other, None),
+            Err(TensorError::BadMetadata.into())
+        );
+        assert!(crate::test_utils::take_invoked_instructions().is_empty());
+    }
+
+    #[test]
+    fn test_transfer_detect() {
+        crate::test_utils::setup_syscall_stubs();
+
+        // (token standard, pNFT transfer)
+        for (token_standard, programmable) in [
+            (None, false),
+            (Some(TokenStandard::NonFungible), false),
+            (Some(TokenStandard::ProgrammableNonFungible), true),
+        ] {
+            let ctx = mock_context_with_standard(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                0,
+                token_standard,
+            );
+
+            assert_eq!(
+                transfer_detect(transfer_args_from_ctx!(ctx), None).unwrap(),
+                token_standard
+            );
+
+            let invoked = crate::test_utils::take_invoked_instructions();
+            assert_eq!(invoked.len(), 1);
+            let expected_program = if programmable {
+                mpl_token_metadata::ID
+            } else {
+                spl_token::ID
+            };
+            assert_eq!(invoked[0].program_id, expected_program);
+        }
+    }
+
+    #[test]
+    fn test_transfer_many() {
+        crate::test_utils::setup_syscall_stubs();
+
+        let pnft = mock_context_with_standard(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            0,
+            Some(TokenStandard::ProgrammableNonFungible),
+        );
+        let nft = mock_context(Pubkey::new_unique(), Pubkey::new_unique(), 0);
+
+        transfer_many(
+            &[transfer_args_from_ctx!(pnft), transfer_args_from_ctx!(nft)],
+            None,
+        )
+        .unwrap();
+
+        let invoked = crate::test_utils::take_invoked_instructions();
+        assert_eq!(
+            invoked
+                .iter()
+                .map(|instruction| instruction.program_id)
+                .collect::<Vec<_>>(),
+            vec![mpl_token_metadata::ID, spl_token::ID]
+        );
+
+        // the second transfer has the metadata of another mint
+        let mut invalid = transfer_args_from_ctx!(nft);
+        invalid.metadata = &pnft.accounts.metadata;
+
+        let error = transfer_many(&[transfer_args_from_ctx!(pnft), invalid], None).unwrap_err();
+        assert_eq!(error, TensorError::TransferFailedAtIndex.into());
+        assert!(matches!(
+            error,
+            Error::AnchorError(error) if matches!(
+                &error.compared_values,
+                Some(ComparedValues::Values((name, index))) if name == "index" && index == "1"
+            )
+        ));
+        assert!(transfer_many(&[], None).is_ok());
+    }
+
+    #[test]
+    fn test_treat_as_nft() {
+        let mut metadata = test_metadata();
+        metadata.creators = Some(vec![Creator {
+            address: Pubkey::new_from_array([4u8; 32]),
+            verified: true,
+            share: 100,
+        }]);
+
+        // legacy NFT with no token standard
+        assert!(treat_as_nft(&metadata, 1, 0));
+        // not NFT-like supply or decimals
+        assert!(!treat_as_nft(&metadata, 2, 0));
+        assert!(!treat_as_nft(&metadata, 1, 6));
+
+        // legacy NFT-like mint without creators
+        metadata.creators = Some(vec![]);
+        assert!(!treat_as_nft(&metadata, 1, 0));
+        metadata.creators = None;
+        assert!(!treat_as_nft(&metadata, 1, 0));
+
+        // explicit token standard takes precedence
+        metadata.token_standard = Some(TokenStandard::NonFungible);
+        assert!(treat_as_nft(&metadata, 1, 0));
+        metadata.token_standard = Some(TokenStandard::ProgrammableNonFungible);
+        assert!(treat_as_nft(&metadata, 1, 0));
+        metadata.token_standard = Some(TokenStandard::Fungible);
+        assert!(!treat_as_nft(&metadata, 1, 0));
+    }
+
+    #[test]
+    fn test_with_authorization_amount() {
+        let data = with_authorization_amount(None, 1).unwrap();
+        assert_eq!(
+            data.payload.map.get(AMOUNT_PAYLOAD_KEY),
+            Some(&PayloadType::Number(1))
+        );
+
+        // matching amount is kept
+        let data = with_authorization_amount(Some(data), 1).unwrap();
+        assert_eq!(data.payload.map.len(), 1);
+        assert_eq!(
+            data.payload.map.get(AMOUNT_PAYLOAD_KEY),
+            Some(&PayloadType::Number(1))
+        );
+
+        // mismatching amount fails
+        assert!(with_authorization_amount(Some(data), 2).is_err());
     }
 }
@@ -1,6 +1,257 @@
 //! Various assertions.
 #![allow(clippy::all)]
 
+use anchor_lang::prelude::*;
+
+use crate::VipersError;
+
+/// Asserts that all accounts are owned by the given program.
+///
+/// Logs the index of the first account that is not owned by the program.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # use tensor_vipers::assert::assert_all_owned_by;
+/// # #[macro_use] extern crate tensor_vipers; fn main() {
+/// let owner = anchor_lang::solana_program::system_program::ID;
+/// let other = anchor_lang::solana_program::sysvar::ID;
+/// let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+/// let (mut lamports_a, mut lamports_b) = (0, 0);
+/// let (mut data_a, mut data_b) = (vec![], vec![]);
+/// let mut accounts = vec![
+///     AccountInfo::new(&keys[0], false, false, &mut lamports_a, &mut data_a, &owner, false, 0),
+///     AccountInfo::new(&keys[1], false, false, &mut lamports_b, &mut data_b, &owner, false, 0),
+/// ];
+///
+/// assert_does_not_throw!({
+///   assert_all_owned_by(&accounts, &owner)?;
+/// });
+///
+/// accounts[1].owner = &other;
+/// assert_throws!({
+///   assert_all_owned_by(&accounts, &owner)?;
+/// }, tensor_vipers::VipersError::OwnerMismatch);
+/// # }
+/// ```
+pub fn assert_all_owned_by(accounts: &[AccountInfo], owner: &Pubkey) -> Result<()> {
+    if let Some((index, account)) = accounts
+        .iter()
+        .enumerate()
+        .find(|(_, account)| account.owner != owner)
+    {
+        msg!(
+            "Owner mismatch: account {} at index {}: expected {}, got {}",
+            account.key,
+            index,
+            owner,
+            account.owner
+        );
+        return Err(error!(VipersError::OwnerMismatch));
+    }
+
+    Ok(())
+}
+
+/// Asserts that a key is in a slice of keys sorted in ascending order, using a binary search.
+///
+/// The slice must be sorted: in debug builds, an unsorted slice panics; in release builds,
+/// the result is unspecified.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # use tensor_vipers::assert::assert_key_in_sorted;
+/// # #[macro_use] extern crate tensor_vipers; fn main() {
+/// let mut keys = vec![Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+/// keys.sort();
+///
+/// assert_does_not_throw!({
+///   assert_key_in_sorted(&keys[1], &keys, tensor_vipers::VipersError::KeyMismatch)?;
+/// });
+///
+/// assert_throws!({
+///   assert_key_in_sorted(&Pubkey::new_unique(), &keys, tensor_vipers::VipersError::KeyMismatch)?;
+/// }, tensor_vipers::VipersError::KeyMismatch);
+/// # }
+/// ```
+///
+/// Unsorted slices are rejected in debug builds:
+///
+/// ```should_panic
+/// # use anchor_lang::prelude::*;
+/// # use tensor_vipers::assert::assert_key_in_sorted;
+/// let mut keys = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+/// keys.sort();
+/// keys.reverse();
+///
+/// let _ = assert_key_in_sorted(&keys[0], &keys, tensor_vipers::VipersError::KeyMismatch);
+/// ```
+pub fn assert_key_in_sorted<E: Into<anchor_lang::error::Error>>(
+    key: &Pubkey,
+    sorted_keys: &[Pubkey],
+    err: E,
+) -> Result<()> {
+    debug_assert!(
+        sorted_keys.windows(2).all(|pair| pair[0] <= pair[1]),
+        "keys must be sorted"
+    );
+
+    if sorted_keys.binary_search(key).is_err() {
+        msg!("Key {} not found", key);
+        return Err(err.into());
+    }
+
+    Ok(())
+}
+
+/// Returns whether an account is empty, i.e. it has not been created yet: it is owned by the
+/// system program, has zero lamports and no data.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # use tensor_vipers::assert::is_account_empty;
+/// let key = Pubkey::new_unique();
+/// let system_program = anchor_lang::solana_program::system_program::ID;
+/// let (mut lamports, mut data) = (0, vec![]);
+/// let mut account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &system_program, false, 0);
+///
+/// assert!(is_account_empty(&account));
+///
+/// **account.lamports.borrow_mut() = 1;
+/// assert!(!is_account_empty(&account));
+///
+/// **account.lamports.borrow_mut() = 0;
+/// account.owner = &key;
+/// assert!(!is_account_empty(&account));
+/// ```
+pub fn is_account_empty(account: &AccountInfo) -> bool {
+    account.owner == &anchor_lang::solana_program::system_program::ID
+        && account.lamports() == 0
+        && account.data_is_empty()
+}
+
+/// Asserts that an account is empty (see [is_account_empty]), e.g. before creating it.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # use tensor_vipers::assert::assert_account_empty;
+/// # #[macro_use] extern crate tensor_vipers; fn main() {
+/// let key = Pubkey::new_unique();
+/// let system_program = anchor_lang::solana_program::system_program::ID;
+/// let (mut lamports, mut data) = (0, vec![]);
+/// let (mut other_lamports, mut other_data) = (0, vec![0u8; 8]);
+/// let empty = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &system_program, false, 0);
+/// let with_data = AccountInfo::new(&key, false, true, &mut other_lamports, &mut other_data, &system_program, false, 0);
+///
+/// assert_does_not_throw!({
+///   assert_account_empty(&empty)?;
+/// });
+///
+/// assert_throws!({
+///   assert_account_empty(&with_data)?;
+/// }, tensor_vipers::VipersError::AccountNotEmpty);
+/// # }
+/// ```
+pub fn assert_account_empty(account: &AccountInfo) -> Result<()> {
+    if !is_account_empty(account) {
+        msg!(
+            "Account {} is not empty: owner {}, lamports {}, data length {}",
+            account.key,
+            account.owner,
+            account.lamports(),
+            account.data_len()
+        );
+        return Err(error!(VipersError::AccountNotEmpty));
+    }
+
+    Ok(())
+}
+
+/// Asserts that the first byte of the account data is the expected one-byte discriminator,
+/// failing if the account has no data.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # use tensor_vipers::assert::assert_discriminator_u8;
+/// # #[macro_use] extern crate tensor_vipers; fn main() {
+/// let key = Pubkey::new_unique();
+/// let (mut lamports, mut data) = (0, vec![1u8, 0, 0]);
+/// let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false, 0);
+///
+/// assert_does_not_throw!({
+///   assert_discriminator_u8(&account, 1)?;
+/// });
+///
+/// assert_throws!({
+///   assert_discriminator_u8(&account, 2)?;
+/// }, tensor_vipers::VipersError::DiscriminatorMismatch);
+/// # }
+/// ```
+pub fn assert_discriminator_u8(account: &AccountInfo, expected: u8) -> Result<()> {
+    let data = account.try_borrow_data()?;
+
+    match data.first() {
+        Some(discriminator) if *discriminator == expected => Ok(()),
+        actual => {
+            msg!(
+                "Account {} discriminator mismatch: expected {}, got {:?}",
+                account.key,
+                expected,
+                actual
+            );
+            Err(error!(VipersError::DiscriminatorMismatch))
+        }
+    }
+}
+
+/// Asserts that the first 8 bytes of the account data are the expected discriminator, e.g.
+/// an Anchor account discriminator, failing if the account has less than 8 bytes of data.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # use tensor_vipers::assert::assert_discriminator;
+/// # #[macro_use] extern crate tensor_vipers; fn main() {
+/// let key = Pubkey::new_unique();
+/// let (mut lamports, mut data) = (0, vec![1u8, 2, 3, 4, 5, 6, 7, 8, 0]);
+/// let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false, 0);
+///
+/// assert_does_not_throw!({
+///   assert_discriminator(&account, &[1, 2, 3, 4, 5, 6, 7, 8])?;
+/// });
+///
+/// assert_throws!({
+///   assert_discriminator(&account, &[8, 7, 6, 5, 4, 3, 2, 1])?;
+/// }, tensor_vipers::VipersError::DiscriminatorMismatch);
+/// # }
+/// ```
+pub fn assert_discriminator(account: &AccountInfo, expected: &[u8; 8]) -> Result<()> {
+    let data = account.try_borrow_data()?;
+
+    match data.get(..8) {
+        Some(discriminator) if discriminator == expected => Ok(()),
+        actual => {
+            msg!(
+                "Account {} discriminator mismatch: expected {:?}, got {:?}",
+                account.key,
+                expected,
+                actual
+            );
+            Err(error!(VipersError::DiscriminatorMismatch))
+        }
+    }
+}
+
 /// Runs a block, returning a [anchor_lang::prelude::Result<()>].
 #[macro_export]
 macro_rules! test_assertion {
@@ -328,6 +579,61 @@ macro_rules! assert_keys_eq {
     }};
 }
 
+/// Asserts that two accounts share the same key, logging both keys in base58 on mismatch.
+///
+/// Takes the actual key first and the expected key second. Both macros log the keys on mismatch:
+/// [assert_keys_eq] logs the error, the expression and the left and right keys in four separate
+/// lines, while this macro logs a single line with the expression and the keys labelled as
+/// expected and actual, which is easier to read in transaction logs.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[error_code]
+/// # pub enum ErrorCode { MyError }
+/// # #[macro_use] extern crate tensor_vipers; fn main() {
+/// let one = anchor_lang::solana_program::sysvar::clock::ID;
+/// let two = anchor_lang::solana_program::system_program::ID;
+///
+/// assert_does_not_throw!({
+///   assert_keys_eq_verbose!(one, one);
+/// });
+///
+/// assert_throws!({
+///   assert_keys_eq_verbose!(one, two);
+/// }, tensor_vipers::VipersError::KeyMismatch);
+///
+/// assert_throws!({
+///   assert_keys_eq_verbose!(one, two, tensor_vipers::VipersError::ProgramIDMismatch);
+/// }, tensor_vipers::VipersError::ProgramIDMismatch);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_keys_eq_verbose {
+    ($actual: expr, $expected: expr $(,)?) => {
+        $crate::assert_keys_eq_verbose!($actual, $expected, $crate::VipersError::KeyMismatch);
+    };
+    ($actual: expr, $expected: expr, $err_code: ident $(,)?) => {
+        $crate::assert_keys_eq_verbose!($actual, $expected, crate::ErrorCode::$err_code);
+    };
+    ($actual: expr, $expected: expr, $err: expr $(,)?) => {{
+        let __actual = &$actual;
+        let __expected = &$expected;
+        let __actual_key = $crate::AsKeyRef::as_key_ref(__actual);
+        let __expected_key = $crate::AsKeyRef::as_key_ref(__expected);
+        if __actual_key != __expected_key {
+            msg!(
+                "Key mismatch: {}: expected {}, got {}",
+                stringify!($actual == $expected),
+                __expected_key,
+                __actual_key
+            );
+            $crate::throw_err!($err);
+        }
+    }};
+}
+
 /// Asserts that a token account is "zero".
 ///
 /// This means that:
@@ -458,6 +764,54 @@ macro_rules! assert_keys_neq {
     }};
 }
 
+/// Asserts that a key is one of the given keys.
+///
+/// On failure, logs the key and the full list of acceptable keys.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # #[macro_use] extern crate tensor_vipers; fn main() {
+/// let system = anchor_lang::solana_program::system_program::ID;
+/// let clock = anchor_lang::solana_program::sysvar::clock::ID;
+/// let rent = anchor_lang::solana_program::sysvar::rent::ID;
+///
+/// assert_does_not_throw!({
+///   assert_key_one_of!(clock, [system, clock]);
+/// });
+///
+/// assert_throws!({
+///   assert_key_one_of!(rent, [system, clock]);
+/// }, tensor_vipers::VipersError::KeyMismatch);
+///
+/// assert_throws!({
+///   assert_key_one_of!(rent, [system, clock], tensor_vipers::VipersError::ProgramIDMismatch);
+/// }, tensor_vipers::VipersError::ProgramIDMismatch);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_key_one_of {
+    ($key: expr, [$($expected: expr),+ $(,)?] $(,)?) => {
+        $crate::assert_key_one_of!($key, [$($expected),+], $crate::VipersError::KeyMismatch);
+    };
+    ($key: expr, [$($expected: expr),+ $(,)?], $err_code: ident $(,)?) => {
+        $crate::assert_key_one_of!($key, [$($expected),+], crate::ErrorCode::$err_code);
+    };
+    ($key: expr, [$($expected: expr),+ $(,)?], $err: expr $(,)?) => {{
+        let __key_ref = &$key;
+        let __key = $crate::AsKeyRef::as_key_ref(__key_ref);
+        if !(false $(|| __key == $crate::AsKeyRef::as_key_ref(&$expected))+) {
+            msg!($crate::format_err!($err));
+            msg!("Key {} is not one of:", __key);
+            $(
+                msg!("{}: {}", stringify!($expected), $crate::AsKeyRef::as_key_ref(&$expected));
+            )+
+            $crate::throw_err!($err);
+        }
+    }};
+}
+
 /// Ensures an [Option] can be unwrapped, otherwise returns the error.
 ///
 /// # Example
@@ -692,3 +1046,123 @@ macro_rules! assert_keys {
         $crate::assert_keys_eq!($account_a, $account_b, $msg)
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, sync::Once};
+
+    use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+
+    use super::*;
+    use crate::test_utils::account_info;
+
+    thread_local! {
+        static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Syscall stubs that record the messages logged by the current thread.
+    struct LogStubs;
+
+    impl SyscallStubs for LogStubs {
+        fn sol_log(&self, message: &str) {
+            LOGS.with(|logs| logs.borrow_mut().push(message.to_string()));
+        }
+    }
+
+    fn take_logs() -> Vec<String> {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            set_syscall_stubs(Box::new(LogStubs));
+        });
+        LOGS.with(|logs| logs.take())
+    }
+
+    fn account_with_data(data: Vec<u8>) -> AccountInfo<'static> {
+        let key = Pubkey::new_unique();
+        account_info(key, key, data)
+    }
+
+    #[test]
+    fn test_assert_discriminator_u8() {
+        let account = account_with_data(vec![3]);
+        crate::assert_does_not_throw!({
+            assert_discriminator_u8(&account, 3)?;
+        });
+        crate::assert_throws!(
+            {
+                assert_discriminator_u8(&account, 4)?;
+            },
+            VipersError::DiscriminatorMismatch
+        );
+
+        // too short
+        let account = account_with_data(vec![]);
+        crate::assert_throws!(
+            {
+                assert_discriminator_u8(&account, 0)?;
+            },
+            VipersError::DiscriminatorMismatch
+        );
+    }
+
+    #[test]
+    fn test_assert_discriminator() {
+        let discriminator = [1, 2, 3, 4, 5, 6, 7, 8];
+
+        // exactly the discriminator, and followed by account data
+        for data in [
+            discriminator.to_vec(),
+            [&discriminator[..], &[9; 4]].concat(),
+        ] {
+            let account = account_with_data(data);
+            crate::assert_does_not_throw!({
+                assert_discriminator(&account, &discriminator)?;
+            });
+        }
+
+        let account = account_with_data([[0; 8], discriminator].concat());
+        crate::assert_throws!(
+            {
+                assert_discriminator(&account, &discriminator)?;
+            },
+            VipersError::DiscriminatorMismatch
+        );
+
+        // too short: 7 bytes and no data
+        for data in [discriminator[..7].to_vec(), vec![]] {
+            let account = account_with_data(data);
+            crate::assert_throws!(
+                {
+                    assert_discriminator(&account, &discriminator)?;
+                },
+                VipersError::DiscriminatorMismatch
+            );
+        }
+    }
+
+    #[test]
+    fn test_assert_keys_eq_verbose_logs_both_keys() {
+        take_logs();
+
+        let expected = Pubkey::new_unique();
+        let actual = Pubkey::new_unique();
+
+        crate::assert_throws!(
+            {
+                assert_keys_eq_verbose!(actual, expected);
+            },
+            VipersError::KeyMismatch
+        );
+
+        let logs = take_logs().join("\n");
+        assert!(logs.contains(&expected.to_string()));
+        assert!(logs.contains(&actual.to_string()));
+        assert!(logs.contains("actual == expected"));
+
+        // nothing is logged when the keys match
+        crate::assert_does_not_throw!({
+            assert_keys_eq_verbose!(actual, actual);
+        });
+        assert!(take_logs().is_empty());
+    }
+}
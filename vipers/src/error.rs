@@ -29,6 +29,24 @@ pub enum VipersError {
     TokenAccountIsNonZero,
     #[msg("Bump not found.")]
     UnknownBump,
+    #[msg("Key must be on the ed25519 curve.")]
+    KeyNotOnCurve,
+    #[msg("Key must not be on the ed25519 curve.")]
+    KeyOnCurve,
+    #[msg("Account must be empty: owned by the system program, without lamports and data.")]
+    AccountNotEmpty,
+    #[msg("Program derived address does not match the seeds.")]
+    PDAMismatch,
+    #[msg("Account discriminator does not match.")]
+    DiscriminatorMismatch,
+}
+
+/// Allows returning a [VipersError] from non-Anchor handlers; the custom error code is the
+/// same as the Anchor error code.
+impl From<VipersError> for ProgramError {
+    fn from(error: VipersError) -> Self {
+        anchor_lang::error::Error::from(error).into()
+    }
 }
 
 /// Conversions into a [CmpError].
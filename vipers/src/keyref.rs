@@ -8,6 +8,12 @@ pub trait AsKeyRef {
     fn as_key_ref(&self) -> &Pubkey;
 }
 
+impl<T: AsKeyRef + ?Sized> AsKeyRef for &T {
+    fn as_key_ref(&self) -> &Pubkey {
+        (**self).as_key_ref()
+    }
+}
+
 impl AsKeyRef for Pubkey {
     fn as_key_ref(&self) -> &Pubkey {
         self
@@ -66,3 +72,41 @@ impl<'info> AsKeyRef for UncheckedAccount<'info> {
         self.as_ref().key
     }
 }
+
+/// Fetches the Pubkey of an optional account or key as a reference, failing if it is missing.
+///
+/// # Example
+///
+/// ```
+/// # use anchor_lang::prelude::*;
+/// # use tensor_vipers::TryAsKeyRef;
+/// # #[macro_use] extern crate tensor_vipers; fn main() {
+/// let key = Pubkey::new_unique();
+/// let some: Option<Pubkey> = Some(key);
+/// let none: Option<Pubkey> = None;
+///
+/// assert_eq!(some.try_as_key_ref().unwrap(), &key);
+///
+/// assert_does_not_throw!({
+///   assert_keys_eq!(some.try_as_key_ref()?, key);
+/// });
+///
+/// assert_throws!({
+///   assert_keys_eq!(none.try_as_key_ref()?, key);
+/// }, tensor_vipers::VipersError::OptionUnwrapFailed);
+/// # }
+/// ```
+pub trait TryAsKeyRef {
+    /// Returns the [Pubkey] of the account or key as a reference, or an
+    /// [crate::VipersError::OptionUnwrapFailed] error if it is `None`.
+    fn try_as_key_ref(&self) -> Result<&Pubkey>;
+}
+
+impl<T: AsKeyRef> TryAsKeyRef for Option<T> {
+    fn try_as_key_ref(&self) -> Result<&Pubkey> {
+        match self {
+            Some(value) => Ok(value.as_key_ref()),
+            None => Err(error!(crate::VipersError::OptionUnwrapFailed)),
+        }
+    }
+}
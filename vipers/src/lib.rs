@@ -5,11 +5,13 @@
 pub mod assert;
 mod error;
 mod keyref;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub mod validate;
 
 use anchor_lang::prelude::*;
 pub use error::*;
-pub use keyref::AsKeyRef;
+pub use keyref::{AsKeyRef, TryAsKeyRef};
 #[cfg(feature = "spl-associated-token-account")]
 pub use spl_associated_token_account as ata;
 pub use validate::Validate;
@@ -44,6 +46,160 @@ pub fn validate_derived_address(
     }
 }
 
+/// Validates a derived program address against its base seeds (without the bump), returning
+/// the canonical bump if the address matches. Like [validate_derived_address], it does not log
+/// on mismatch.
+///
+/// Use [validate_derived_address] when the seeds already include the bump, or [validate_pda]
+/// to get an error instead of `None` on mismatch.
+///
+/// # Example
+///
+/// ```
+/// use tensor_vipers::validate_derived_address_with_bump;
+/// use anchor_lang::prelude::*;
+/// let seeds: &[&[u8]] = &[b"test"];
+/// let (expected, bump) = Pubkey::find_program_address(seeds, &tensor_vipers::ID);
+/// assert_eq!(
+///   validate_derived_address_with_bump(&expected, &tensor_vipers::ID, seeds),
+///   Some(bump)
+/// );
+/// assert_eq!(
+///   validate_derived_address_with_bump(&Pubkey::default(), &tensor_vipers::ID, seeds),
+///   None
+/// );
+/// ```
+pub fn validate_derived_address_with_bump(
+    derived_address: &Pubkey,
+    program_id: &Pubkey,
+    base_seeds: &[&[u8]],
+) -> Option<u8> {
+    let (expected, bump) = Pubkey::find_program_address(base_seeds, program_id);
+    (derived_address == &expected).then_some(bump)
+}
+
+/// Asserts that the seeds and a stored bump produce the expected program address.
+///
+/// The bump is appended to the seeds before deriving the address.
+///
+/// # Example
+///
+/// ```
+/// use tensor_vipers::{assert_pda_with_bump, VipersError};
+/// use anchor_lang::prelude::*;
+/// let seeds: &[&[u8]] = &[b"test"];
+/// let (expected, bump) = Pubkey::find_program_address(seeds, &tensor_vipers::ID);
+/// assert!(assert_pda_with_bump(&expected, &tensor_vipers::ID, seeds, bump).is_ok());
+/// assert!(assert_pda_with_bump(&expected, &tensor_vipers::ID, seeds, bump.wrapping_sub(1)).is_err());
+/// assert_eq!(
+///     assert_pda_with_bump(&Pubkey::default(), &tensor_vipers::ID, seeds, bump).unwrap_err(),
+///     error!(VipersError::PDAMismatch)
+/// );
+/// ```
+pub fn assert_pda_with_bump(
+    expected: &Pubkey,
+    program_id: &Pubkey,
+    seeds: &[&[u8]],
+    bump: u8,
+) -> Result<()> {
+    let bump = [bump];
+    let mut seeds_with_bump = seeds.to_vec();
+    seeds_with_bump.push(&bump);
+
+    if !validate_derived_address(expected, program_id, &seeds_with_bump) {
+        msg!("PDA mismatch: expected {}", expected);
+        return Err(error!(VipersError::PDAMismatch));
+    }
+
+    Ok(())
+}
+
+/// Validates that a key is the canonical program address of the seeds, returning the bump.
+///
+/// The bump can be stored to later re-derive the address more cheaply with
+/// [assert_pda_with_bump].
+///
+/// # Example
+///
+/// ```
+/// use tensor_vipers::{validate_pda, VipersError};
+/// use anchor_lang::prelude::*;
+/// let seeds: &[&[u8]] = &[b"test"];
+/// let (expected, bump) = Pubkey::find_program_address(seeds, &tensor_vipers::ID);
+/// assert_eq!(validate_pda(&expected, &tensor_vipers::ID, seeds).unwrap(), bump);
+/// assert_eq!(
+///     validate_pda(&Pubkey::default(), &tensor_vipers::ID, seeds).unwrap_err(),
+///     error!(VipersError::PDAMismatch)
+/// );
+/// ```
+pub fn validate_pda<T: AsKeyRef>(actual: &T, program_id: &Pubkey, seeds: &[&[u8]]) -> Result<u8> {
+    let (expected, bump) = Pubkey::find_program_address(seeds, program_id);
+    let actual = actual.as_key_ref();
+
+    if actual != &expected {
+        msg!("PDA mismatch: expected {}, got {}", expected, actual);
+        return Err(error!(VipersError::PDAMismatch));
+    }
+
+    Ok(bump)
+}
+
+/// Returns true if the key is a point on the ed25519 curve, i.e. an address that can be
+/// controlled by a keypair. Program derived addresses are never on the curve.
+///
+/// # Example
+///
+/// ```
+/// use tensor_vipers::is_on_curve;
+/// use anchor_lang::prelude::*;
+/// let (pda, _) = Pubkey::find_program_address(&[b"test"], &tensor_vipers::ID);
+/// assert!(!is_on_curve(&pda));
+/// assert!(is_on_curve(&tensor_vipers::ID));
+/// ```
+pub fn is_on_curve(key: &Pubkey) -> bool {
+    key.is_on_curve()
+}
+
+/// Asserts that the key is on the ed25519 curve (a wallet address, not a PDA).
+///
+/// # Example
+///
+/// ```
+/// use tensor_vipers::assert_on_curve;
+/// use anchor_lang::prelude::*;
+/// let (pda, _) = Pubkey::find_program_address(&[b"test"], &tensor_vipers::ID);
+/// assert!(assert_on_curve(&tensor_vipers::ID).is_ok());
+/// assert!(assert_on_curve(&pda).is_err());
+/// ```
+pub fn assert_on_curve(key: &Pubkey) -> Result<()> {
+    if !is_on_curve(key) {
+        msg!("Key is not on curve: {}", key);
+        return Err(error!(VipersError::KeyNotOnCurve));
+    }
+
+    Ok(())
+}
+
+/// Asserts that the key is not on the ed25519 curve (e.g. a PDA).
+///
+/// # Example
+///
+/// ```
+/// use tensor_vipers::assert_off_curve;
+/// use anchor_lang::prelude::*;
+/// let (pda, _) = Pubkey::find_program_address(&[b"test"], &tensor_vipers::ID);
+/// assert!(assert_off_curve(&pda).is_ok());
+/// assert!(assert_off_curve(&tensor_vipers::ID).is_err());
+/// ```
+pub fn assert_off_curve(key: &Pubkey) -> Result<()> {
+    if is_on_curve(key) {
+        msg!("Key is on curve: {}", key);
+        return Err(error!(VipersError::KeyOnCurve));
+    }
+
+    Ok(())
+}
+
 /// Helper for getting the current timestamp.
 pub fn now_i64() -> Result<i64> {
     Ok(Clock::get()?.unix_timestamp)
@@ -56,12 +212,64 @@ pub fn now<T: TryFrom<i64>>() -> Result<T> {
         .map_err(|_| ::anchor_lang::prelude::error!(VipersError::IntegerOverflow))
 }
 
+/// Helper for getting the current slot.
+///
+/// # Example
+///
+/// ```
+/// use tensor_vipers::current_slot;
+/// use anchor_lang::prelude::*;
+/// fn is_expired(expiry_slot: u64) -> Result<bool> {
+///     Ok(current_slot()? > expiry_slot)
+/// }
+/// ```
+pub fn current_slot() -> Result<u64> {
+    Ok(Clock::get()?.slot)
+}
+
+/// Helper for getting the current epoch.
+///
+/// # Example
+///
+/// ```
+/// use tensor_vipers::current_epoch;
+/// use anchor_lang::prelude::*;
+/// fn is_current_epoch(epoch: u64) -> Result<bool> {
+///     Ok(current_epoch()? == epoch)
+/// }
+/// ```
+pub fn current_epoch() -> Result<u64> {
+    Ok(Clock::get()?.epoch)
+}
+
+/// Helper for getting the current unix timestamp, slot and epoch (in this order) with a single
+/// read of the `Clock` sysvar.
+///
+/// # Example
+///
+/// ```
+/// use tensor_vipers::clock_snapshot;
+/// use anchor_lang::prelude::*;
+/// fn log_clock() -> Result<()> {
+///     let (timestamp, slot, epoch) = clock_snapshot()?;
+///     msg!("timestamp: {}, slot: {}, epoch: {}", timestamp, slot, epoch);
+///     Ok(())
+/// }
+/// ```
+pub fn clock_snapshot() -> Result<(i64, u64, u64)> {
+    let clock = Clock::get()?;
+    Ok((clock.unix_timestamp, clock.slot, clock.epoch))
+}
+
 pub mod prelude {
     //! The prelude contains all commonly used components of the crate. All programs should include it via `use tensor_vipers::prelude::*;`.
 
     pub use super::{
-        assert_is_zero_token_account, assert_keys_eq, assert_keys_neq, invariant, now, now_i64,
-        try_or_err, unwrap_bump, unwrap_checked, unwrap_int, unwrap_opt, unwrap_opt_block,
-        unwrap_or_err, AsKeyRef, CmpError, IntoCmpError, Validate, VipersError,
+        assert_is_zero_token_account, assert_keys_eq, assert_keys_eq_verbose, assert_keys_neq,
+        clock_snapshot, current_epoch, current_slot, invariant, now, now_i64, try_or_err,
+        unwrap_bump, unwrap_checked, unwrap_int, unwrap_opt, unwrap_opt_block, unwrap_or_err,
+        AsKeyRef, CmpError, IntoCmpError, TryAsKeyRef, Validate, VipersError,
     };
+
+    pub use super::assert::{assert_all_owned_by, assert_key_in_sorted};
 }
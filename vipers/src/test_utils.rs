@@ -0,0 +1,17 @@
+use anchor_lang::solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+
+/// Creates a writable account without lamports with the given key, owner and data.
+///
+/// The account memory is leaked, so the account can be used as `AccountInfo<'static>`.
+pub fn account_info(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> AccountInfo<'static> {
+    AccountInfo::new(
+        Box::leak(Box::new(key)),
+        false,
+        true,
+        Box::leak(Box::new(0)),
+        Box::leak(data.into_boxed_slice()),
+        Box::leak(Box::new(owner)),
+        false,
+        0,
+    )
+}